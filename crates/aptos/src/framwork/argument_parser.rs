@@ -0,0 +1,436 @@
+use crate::types::transaction::ArgumentABI;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use move_core_types::value::{MoveStruct, MoveValue};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// An argument which does not fit the type of the parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentError {
+    /// The name of the parameter, followed by the indices of the vector elements which lead
+    /// to the offending value, e.g. `amounts[2]`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ArgumentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid argument `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
+/// Parses the arguments of entry functions and scripts according to the types of their
+/// parameters, and BCS encodes them.
+///
+/// Arguments are given as JSON values or as strings from the command line:
+/// - integers as JSON numbers or decimal strings, optionally with a Move suffix like `5u64`;
+/// - addresses as hex literals, optionally prefixed with `@`, or as named addresses;
+/// - `vector<u8>` as a hex string `0x0a0b`, `x"0a0b"`, `b"text"` or an array of bytes;
+/// - other vectors as JSON arrays, which may be nested;
+/// - `0x1::string::String` as plain text or `b"text"`;
+/// - `0x1::object::Object<T>` as the address of the object.
+#[derive(Debug, Clone, Default)]
+pub struct ArgumentParser {
+    named_addresses: BTreeMap<String, AccountAddress>,
+}
+
+impl ArgumentParser {
+    pub fn new(named_addresses: BTreeMap<String, AccountAddress>) -> Self {
+        Self { named_addresses }
+    }
+
+    /// Parses a command line argument and BCS encodes it.
+    pub fn parse_str(&self, name: &str, ty: &TypeTag, arg: &str) -> Result<Vec<u8>, ArgumentError> {
+        serialize(name, self.parse_str_value(name, ty, arg)?)
+    }
+
+    /// Parses a JSON argument and BCS encodes it.
+    pub fn parse_json(
+        &self,
+        name: &str,
+        ty: &TypeTag,
+        arg: &Value,
+    ) -> Result<Vec<u8>, ArgumentError> {
+        serialize(name, self.parse_value(name, ty, arg)?)
+    }
+
+    pub fn parse_abi_str(&self, abi: &ArgumentABI, arg: &str) -> Result<Vec<u8>, ArgumentError> {
+        self.parse_str(abi.name(), abi.type_tag(), arg)
+    }
+
+    pub fn parse_abi_json(&self, abi: &ArgumentABI, arg: &Value) -> Result<Vec<u8>, ArgumentError> {
+        self.parse_json(abi.name(), abi.type_tag(), arg)
+    }
+
+    /// Parses a command line argument into a Move value of type `ty`. Vectors other than
+    /// `vector<u8>` are given as JSON arrays, e.g. `[1, 2, 3]` or `["0x1", "alice"]`.
+    pub fn parse_str_value(
+        &self,
+        name: &str,
+        ty: &TypeTag,
+        arg: &str,
+    ) -> Result<MoveValue, ArgumentError> {
+        let value = match ty {
+            TypeTag::Vector(_) if arg.trim_start().starts_with('[') => serde_json::from_str(arg)
+                .map_err(|err| ArgumentError {
+                    path: name.to_string(),
+                    message: format!("expected a JSON array: {}", err),
+                })?,
+            _ => Value::String(arg.to_string()),
+        };
+        self.parse_value(name, ty, &value)
+    }
+
+    /// Parses a JSON value into a Move value of type `ty`.
+    pub fn parse_value(
+        &self,
+        path: &str,
+        ty: &TypeTag,
+        arg: &Value,
+    ) -> Result<MoveValue, ArgumentError> {
+        let err = |message: String| ArgumentError {
+            path: path.to_string(),
+            message,
+        };
+        Ok(match ty {
+            TypeTag::Bool => match arg {
+                Value::Bool(b) => MoveValue::Bool(*b),
+                Value::String(s) if s == "true" => MoveValue::Bool(true),
+                Value::String(s) if s == "false" => MoveValue::Bool(false),
+                _ => return Err(err(format!("expected a bool, got {}", arg))),
+            },
+            TypeTag::U8 => MoveValue::U8(parse_integer(arg, "u8").map_err(err)?),
+            TypeTag::U16 => MoveValue::U16(parse_integer(arg, "u16").map_err(err)?),
+            TypeTag::U32 => MoveValue::U32(parse_integer(arg, "u32").map_err(err)?),
+            TypeTag::U64 => MoveValue::U64(parse_integer(arg, "u64").map_err(err)?),
+            TypeTag::U128 => MoveValue::U128(parse_integer(arg, "u128").map_err(err)?),
+            TypeTag::U256 => MoveValue::U256(parse_integer(arg, "u256").map_err(err)?),
+            TypeTag::Address => MoveValue::Address(self.parse_address(arg).map_err(err)?),
+            TypeTag::Vector(inner) if **inner == TypeTag::U8 => match arg {
+                Value::String(s) => bytes_value(parse_bytes(s).map_err(err)?),
+                _ => self.parse_vector(path, inner, arg)?,
+            },
+            TypeTag::Vector(inner) => self.parse_vector(path, inner, arg)?,
+            TypeTag::Struct(tag) if is_framework_struct(tag, "string", "String") => match arg {
+                Value::String(s) => {
+                    let text = match s.strip_prefix("b\"").and_then(|s| s.strip_suffix('"')) {
+                        Some(text) => text,
+                        None => s.as_str(),
+                    };
+                    MoveValue::Struct(MoveStruct::Runtime(vec![bytes_value(
+                        text.as_bytes().to_vec(),
+                    )]))
+                }
+                _ => return Err(err(format!("expected a string, got {}", arg))),
+            },
+            TypeTag::Struct(tag) if is_framework_struct(tag, "object", "Object") => {
+                MoveValue::Struct(MoveStruct::Runtime(vec![MoveValue::Address(
+                    self.parse_address(arg).map_err(err)?,
+                )]))
+            }
+            TypeTag::Struct(tag) => {
+                return Err(err(format!(
+                    "arguments of type `{}` are not supported, only strings and objects can be \
+                     passed as structs",
+                    tag
+                )))
+            }
+            TypeTag::Signer => {
+                return Err(err("signers cannot be passed as arguments".to_string()))
+            }
+        })
+    }
+
+    fn parse_vector(
+        &self,
+        path: &str,
+        inner: &TypeTag,
+        arg: &Value,
+    ) -> Result<MoveValue, ArgumentError> {
+        match arg {
+            Value::Array(elements) => Ok(MoveValue::Vector(
+                elements
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, element)| {
+                        self.parse_value(&format!("{}[{}]", path, idx), inner, element)
+                    })
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => Err(ArgumentError {
+                path: path.to_string(),
+                message: format!("expected an array of `{}`, got {}", inner, arg),
+            }),
+        }
+    }
+
+    fn parse_address(&self, arg: &Value) -> Result<AccountAddress, String> {
+        let s = match arg {
+            Value::String(s) => s.strip_prefix('@').unwrap_or(s),
+            _ => return Err(format!("expected an address, got {}", arg)),
+        };
+        if let Some(address) = self.named_addresses.get(s) {
+            return Ok(*address);
+        }
+        if !s.starts_with("0x") {
+            return Err(format!(
+                "`{}` is neither a hex literal nor a known named address",
+                s
+            ));
+        }
+        AccountAddress::from_hex_literal(s)
+            .map_err(|err| format!("`{}` is not a valid address: {}", s, err))
+    }
+
+    /// Converts an argument of type `ty` into the JSON format of the Aptos REST API, which
+    /// represents strings as plain text and objects as their address.
+    pub fn to_json(value: &MoveValue, ty: &TypeTag) -> Value {
+        match (value, ty) {
+            (MoveValue::Struct(MoveStruct::Runtime(fields)), TypeTag::Struct(tag)) => {
+                match fields.as_slice() {
+                    [MoveValue::Vector(bytes)] if is_framework_struct(tag, "string", "String") => {
+                        json!(String::from_utf8_lossy(&bytes_of(bytes)))
+                    }
+                    [MoveValue::Address(address)] => json!(address.to_hex_literal()),
+                    _ => crate::vm::move_value_to_json(value.clone()),
+                }
+            }
+            // Bytes are hex encoded even if empty, which `move_value_to_json` cannot tell
+            // from an empty vector of another type.
+            (MoveValue::Vector(values), TypeTag::Vector(inner)) if **inner == TypeTag::U8 => {
+                json!(format!("0x{}", hex::encode(bytes_of(values))))
+            }
+            (MoveValue::Vector(values), TypeTag::Vector(inner)) => {
+                Value::Array(values.iter().map(|v| Self::to_json(v, inner)).collect())
+            }
+            _ => crate::vm::move_value_to_json(value.clone()),
+        }
+    }
+}
+
+fn serialize(name: &str, value: MoveValue) -> Result<Vec<u8>, ArgumentError> {
+    value.simple_serialize().ok_or_else(|| ArgumentError {
+        path: name.to_string(),
+        message: "the value cannot be serialized".to_string(),
+    })
+}
+
+fn is_framework_struct(tag: &StructTag, module: &str, name: &str) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == module && tag.name.as_str() == name
+}
+
+fn bytes_value(bytes: Vec<u8>) -> MoveValue {
+    MoveValue::Vector(bytes.into_iter().map(MoveValue::U8).collect())
+}
+
+fn bytes_of(values: &[MoveValue]) -> Vec<u8> {
+    values
+        .iter()
+        .filter_map(|v| match v {
+            MoveValue::U8(b) => Some(*b),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses `0x0a0b`, `x"0a0b"` or `b"text"`.
+fn parse_bytes(s: &str) -> Result<Vec<u8>, String> {
+    if let Some(text) = s.strip_prefix("b\"").and_then(|s| s.strip_suffix('"')) {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let hex_str = s
+        .strip_prefix("x\"")
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix("0x"))
+        .ok_or_else(|| {
+            format!(
+                "expected bytes as `0x..`, `x\"..\"` or `b\"..\"`, got `{}`",
+                s
+            )
+        })?;
+    hex::decode(hex_str).map_err(|err| format!("`{}` is not valid hex: {}", s, err))
+}
+
+/// Parses an integer from a JSON number or a decimal string, which may carry the Move suffix
+/// of the type, e.g. `5u64`.
+fn parse_integer<T>(arg: &Value, ty: &str) -> Result<T, String>
+where
+    T: FromStr + TryFrom<u64>,
+{
+    let s = match arg {
+        Value::Number(n) => match n.as_u64() {
+            Some(n) => return T::try_from(n).map_err(|_| out_of_range(&n.to_string(), ty)),
+            None => {
+                return Err(format!(
+                    "expected a non-negative integer for `{}`, got {}",
+                    ty, n
+                ))
+            }
+        },
+        Value::String(s) => s.strip_suffix(ty).unwrap_or(s).replace('_', ""),
+        _ => return Err(format!("expected `{}`, got {}", ty, arg)),
+    };
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("`{}` is not a decimal `{}`", s, ty));
+    }
+    s.parse::<T>().map_err(|_| out_of_range(&s, ty))
+}
+
+fn out_of_range(s: &str, ty: &str) -> String {
+    format!("{} does not fit into `{}`", s, ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(inner: TypeTag) -> TypeTag {
+        TypeTag::Vector(Box::new(inner))
+    }
+
+    fn parser() -> ArgumentParser {
+        ArgumentParser::new(BTreeMap::from([(
+            "alice".to_string(),
+            AccountAddress::from_hex_literal("0xa11ce").unwrap(),
+        )]))
+    }
+
+    #[test]
+    fn parses_integers_with_and_without_suffix() {
+        let parser = parser();
+        assert_eq!(
+            parser.parse_str_value("x", &TypeTag::U8, "255").unwrap(),
+            MoveValue::U8(255)
+        );
+        assert_eq!(
+            parser.parse_str_value("x", &TypeTag::U8, "5u8").unwrap(),
+            MoveValue::U8(5)
+        );
+        assert_eq!(
+            parser
+                .parse_str_value("x", &TypeTag::U64, "1_000u64")
+                .unwrap(),
+            MoveValue::U64(1000)
+        );
+        assert_eq!(
+            parser.parse_value("x", &TypeTag::U16, &json!(7)).unwrap(),
+            MoveValue::U16(7)
+        );
+    }
+
+    #[test]
+    fn rejects_u8_overflow() {
+        let parser = parser();
+        let err = parser
+            .parse_str_value("x", &TypeTag::U8, "256")
+            .unwrap_err();
+        assert_eq!(err.path, "x");
+        assert_eq!(err.message, "256 does not fit into `u8`");
+        let err = parser
+            .parse_value("x", &TypeTag::U8, &json!(300))
+            .unwrap_err();
+        assert_eq!(err.message, "300 does not fit into `u8`");
+    }
+
+    #[test]
+    fn rejects_the_suffix_of_another_type() {
+        let err = parser()
+            .parse_str_value("x", &TypeTag::U8, "5u64")
+            .unwrap_err();
+        assert_eq!(err.message, "`5u64` is not a decimal `u8`");
+    }
+
+    #[test]
+    fn parses_bytes() {
+        let parser = parser();
+        let ty = vector(TypeTag::U8);
+        let expected = bytes_value(b"hi".to_vec());
+        assert_eq!(
+            parser.parse_str_value("x", &ty, "b\"hi\"").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parser.parse_str_value("x", &ty, "0x6869").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parser.parse_str_value("x", &ty, "x\"6869\"").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parser.parse_str_value("x", &ty, "[104, 105]").unwrap(),
+            expected
+        );
+        assert!(parser.parse_str_value("x", &ty, "0xzz").is_err());
+        assert_eq!(
+            parser.parse_str("x", &ty, "0x6869").unwrap(),
+            bcs::to_bytes(&b"hi".to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn renders_bytes_as_hex() {
+        let ty = vector(TypeTag::U8);
+        assert_eq!(
+            ArgumentParser::to_json(&bytes_value(vec![]), &ty),
+            json!("0x")
+        );
+        assert_eq!(
+            ArgumentParser::to_json(&bytes_value(b"hi".to_vec()), &ty),
+            json!("0x6869")
+        );
+        let ty = vector(vector(TypeTag::U8));
+        assert_eq!(
+            ArgumentParser::to_json(&MoveValue::Vector(vec![bytes_value(vec![])]), &ty),
+            json!(["0x"])
+        );
+        assert_eq!(
+            ArgumentParser::to_json(&MoveValue::Vector(vec![]), &vector(TypeTag::U64)),
+            json!([])
+        );
+    }
+
+    #[test]
+    fn parses_nested_vectors_of_named_addresses() {
+        let ty = vector(vector(TypeTag::Address));
+        let value = parser()
+            .parse_str_value("x", &ty, r#"[["alice", "@0x1"], [], ["@alice"]]"#)
+            .unwrap();
+        let alice = MoveValue::Address(AccountAddress::from_hex_literal("0xa11ce").unwrap());
+        assert_eq!(
+            value,
+            MoveValue::Vector(vec![
+                MoveValue::Vector(vec![alice.clone(), MoveValue::Address(AccountAddress::ONE)]),
+                MoveValue::Vector(vec![]),
+                MoveValue::Vector(vec![alice]),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_the_path_of_a_bad_element() {
+        let parser = parser();
+        let err = parser
+            .parse_str_value("arg0", &vector(TypeTag::U64), "[1, 2, \"x\"]")
+            .unwrap_err();
+        assert_eq!(err.path, "arg0[2]");
+        assert_eq!(
+            err.to_string(),
+            "invalid argument `arg0[2]`: `x` is not a decimal `u64`"
+        );
+
+        let err = parser
+            .parse_str_value(
+                "arg0",
+                &vector(vector(TypeTag::Address)),
+                r#"[["alice"], ["alice", "bob"]]"#,
+            )
+            .unwrap_err();
+        assert_eq!(err.path, "arg0[1][1]");
+    }
+}
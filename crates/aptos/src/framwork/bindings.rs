@@ -0,0 +1,782 @@
+use super::module_metadata::get_module_metadata;
+use crate::types::transaction::{EntryABI, EntryFunctionABI};
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::file_format::{SignatureToken, StructHandleIndex};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::TypeTag;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+
+/// The name of the file which holds the types shared by all generated modules.
+pub const TS_TYPES_FILE: &str = "_types.ts";
+
+const TS_TYPES: &str = r#"// Generated by `aptos gen-bindings`, do not edit.
+
+/** An integer which may not fit into a JavaScript number. It is passed as a decimal string. */
+export type AnyNumber = number | bigint | string;
+
+/** An account address as a hex string, e.g. `0x1`. */
+export type Address = string;
+
+/** Bytes as a `Uint8Array` or a hex string, e.g. `0x0a0b`. */
+export type Bytes = Uint8Array | string;
+
+/** A type argument, e.g. `0x1::aptos_coin::AptosCoin`. */
+export type TypeTag = string;
+
+/** The payload of a transaction which calls an entry function, as accepted by the REST API. */
+export interface EntryFunctionPayload {
+  type: "entry_function_payload";
+  function: string;
+  type_arguments: TypeTag[];
+  arguments: unknown[];
+}
+
+/** The request of a call to a view function, as accepted by the `/view` endpoint. */
+export interface ViewRequest {
+  function: string;
+  type_arguments: TypeTag[];
+  arguments: unknown[];
+}
+
+export function bytesToHex(bytes: Bytes): string {
+  if (typeof bytes === "string") {
+    return bytes;
+  }
+  return "0x" + Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+"#;
+
+/// Generates TypeScript bindings for the modules of a package. Each module gets a file with
+/// functions which build the payloads of its entry functions, helpers for the type tags of its
+/// structs, and a `Views` interface for its view functions. Returns the files by name, including
+/// the shared [`TS_TYPES_FILE`].
+pub fn generate_ts_bindings(
+    abis: &[EntryABI],
+    modules: &[&CompiledModule],
+) -> BTreeMap<String, String> {
+    let mut files = BTreeMap::new();
+    files.insert(TS_TYPES_FILE.to_string(), TS_TYPES.to_string());
+    for module in modules {
+        let entry_functions = abis
+            .iter()
+            .filter_map(|abi| match abi {
+                EntryABI::EntryFunction(abi) if *abi.module_name() == module.self_id() => Some(abi),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        files.insert(
+            format!("{}.ts", module.self_id().name()),
+            generate_module(module, &entry_functions),
+        );
+    }
+    files
+}
+
+fn generate_module(module: &CompiledModule, entry_functions: &[&EntryFunctionABI]) -> String {
+    let mut imports = BTreeSet::new();
+    let mut body = String::new();
+    write_module(&mut body, &mut imports, module, entry_functions)
+        .expect("writing to a String cannot fail");
+
+    let mut out = format!(
+        "// Generated by `aptos gen-bindings` for module `{}`, do not edit.\n\n",
+        module_name(module)
+    );
+    if !imports.is_empty() {
+        out.push_str(&format!(
+            "import {{ {} }} from \"./_types\";\n",
+            imports.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    out.push_str(&body);
+    out
+}
+
+/// Writes the declarations of a module, collecting the names it uses from `TS_TYPES_FILE`
+/// into `imports`.
+fn write_module(
+    out: &mut String,
+    imports: &mut BTreeSet<&'static str>,
+    module: &CompiledModule,
+    entry_functions: &[&EntryFunctionABI],
+) -> fmt::Result {
+    for handle in module.struct_handles() {
+        if handle.module != module.self_handle_idx() {
+            continue;
+        }
+        let name = module.identifier_at(handle.name).as_str();
+        let tag = format!("{}::{}", module_name(module), name);
+        let name = identifier(name);
+        imports.insert("TypeTag");
+        writeln!(out)?;
+        writeln!(out, "/** The type tag of struct `{}`. */", tag)?;
+        if handle.type_parameters.is_empty() {
+            writeln!(out, "export const {}: TypeTag = \"{}\";", name, tag)?;
+        } else {
+            let params = (0..handle.type_parameters.len())
+                .map(|idx| format!("t{}", idx))
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "export function {}({}): TypeTag {{\n  return `{}<{}>`;\n}}",
+                name,
+                params
+                    .iter()
+                    .map(|param| format!("{}: TypeTag", param))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                tag,
+                params
+                    .iter()
+                    .map(|param| format!("${{{}}}", param))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+    }
+
+    for abi in entry_functions {
+        let mut names = Names::default();
+        let ty_params = abi
+            .ty_args()
+            .iter()
+            .map(|ty_arg| names.fresh(ty_arg.name()))
+            .collect::<Vec<_>>();
+        let params = abi
+            .args()
+            .iter()
+            .map(|arg| {
+                (
+                    names.fresh(arg.name()),
+                    MoveType::from_type_tag(arg.type_tag()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let function_id = function_id(module, abi.name());
+
+        imports.insert("EntryFunctionPayload");
+        writeln!(out)?;
+        write_doc(out, abi.doc(), &function_id)?;
+        writeln!(
+            out,
+            "export function {}({}): EntryFunctionPayload {{",
+            identifier(&camel_case(abi.name())),
+            signature(imports, &ty_params, &params)
+        )?;
+        writeln!(out, "  return {{")?;
+        writeln!(out, "    type: \"entry_function_payload\",")?;
+        writeln!(out, "    function: \"{}\",", function_id)?;
+        writeln!(out, "    type_arguments: [{}],", ty_params.join(", "))?;
+        writeln!(out, "    arguments: [{}],", arguments(&params))?;
+        writeln!(out, "  }};\n}}")?;
+    }
+
+    let views = view_functions(module);
+    if views.is_empty() {
+        return Ok(());
+    }
+    for view in &views {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "/** The result of view function `{}`. */",
+            view.function_id
+        )?;
+        writeln!(
+            out,
+            "export type {}Result = [{}];",
+            pascal_case(&view.name),
+            view.returns
+                .iter()
+                .map(MoveType::output_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/** The view functions of module `{}`. */",
+        module_name(module)
+    )?;
+    writeln!(out, "export interface Views {{")?;
+    for view in &views {
+        writeln!(out, "  /** Calls view function `{}`. */", view.function_id)?;
+        writeln!(
+            out,
+            "  {}({}): Promise<{}Result>;",
+            identifier(&camel_case(&view.name)),
+            signature(imports, &view.ty_params, &view.params),
+            pascal_case(&view.name)
+        )?;
+    }
+    writeln!(out, "}}")?;
+
+    imports.insert("ViewRequest");
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/** Implements `Views` on top of `view`, which sends a request to the `/view` \
+         endpoint and returns the result. */"
+    )?;
+    writeln!(
+        out,
+        "export function views(view: (request: ViewRequest) => Promise<unknown[]>): Views {{"
+    )?;
+    writeln!(out, "  return {{")?;
+    for view in &views {
+        writeln!(
+            out,
+            "    {}: ({}) =>",
+            identifier(&camel_case(&view.name)),
+            signature(imports, &view.ty_params, &view.params)
+        )?;
+        writeln!(
+            out,
+            "      view({{ function: \"{}\", type_arguments: [{}], arguments: [{}] }}) as \
+             Promise<{}Result>,",
+            view.function_id,
+            view.ty_params.join(", "),
+            arguments(&view.params),
+            pascal_case(&view.name)
+        )?;
+    }
+    writeln!(out, "  }};\n}}")
+}
+
+struct ViewFunction {
+    name: String,
+    function_id: String,
+    ty_params: Vec<String>,
+    params: Vec<(String, MoveType)>,
+    returns: Vec<MoveType>,
+}
+
+/// Returns the view functions of the module, as recorded in its metadata.
+fn view_functions(module: &CompiledModule) -> Vec<ViewFunction> {
+    let view_names = get_module_metadata(module)
+        .map(|metadata| {
+            metadata
+                .fun_attributes
+                .into_iter()
+                .filter(|(_, attributes)| attributes.iter().any(|attr| attr.is_view_function()))
+                .map(|(name, _)| name)
+                .collect::<BTreeSet<_>>()
+        })
+        .unwrap_or_default();
+
+    module
+        .function_defs()
+        .iter()
+        .map(|def| module.function_handle_at(def.function))
+        .filter(|handle| view_names.contains(module.identifier_at(handle.name).as_str()))
+        .map(|handle| {
+            let name = module.identifier_at(handle.name).to_string();
+            let ty_params = (0..handle.type_parameters.len())
+                .map(|idx| format!("t{}", idx))
+                .collect();
+            let params = module
+                .signature_at(handle.parameters)
+                .0
+                .iter()
+                .enumerate()
+                .map(|(idx, token)| (format!("arg{}", idx), MoveType::from_token(module, token)))
+                .collect();
+            let returns = module
+                .signature_at(handle.return_)
+                .0
+                .iter()
+                .map(|token| MoveType::from_token(module, token))
+                .collect();
+            ViewFunction {
+                function_id: function_id(module, &name),
+                name,
+                ty_params,
+                params,
+                returns,
+            }
+        })
+        .collect()
+}
+
+/// The Move types as far as they matter for their JSON representation in the REST API.
+enum MoveType {
+    Bool,
+    /// `u8`, `u16` and `u32`, which are JSON numbers
+    SmallInt,
+    /// `u64`, `u128` and `u256`, which are decimal strings
+    BigInt,
+    Address,
+    Bytes,
+    Vector(Box<MoveType>),
+    String,
+    Object,
+    Option(Box<MoveType>),
+    Struct,
+    Other,
+}
+
+impl MoveType {
+    fn from_type_tag(tag: &TypeTag) -> Self {
+        match tag {
+            TypeTag::Bool => MoveType::Bool,
+            TypeTag::U8 | TypeTag::U16 | TypeTag::U32 => MoveType::SmallInt,
+            TypeTag::U64 | TypeTag::U128 | TypeTag::U256 => MoveType::BigInt,
+            TypeTag::Address => MoveType::Address,
+            TypeTag::Vector(inner) if **inner == TypeTag::U8 => MoveType::Bytes,
+            TypeTag::Vector(inner) => MoveType::Vector(Box::new(Self::from_type_tag(inner))),
+            TypeTag::Struct(tag) => Self::from_struct(
+                &tag.address,
+                tag.module.as_str(),
+                tag.name.as_str(),
+                tag.type_params.first().map(Self::from_type_tag),
+            ),
+            TypeTag::Signer => MoveType::Other,
+        }
+    }
+
+    fn from_token(module: &CompiledModule, token: &SignatureToken) -> Self {
+        let from_handle = |idx: &StructHandleIndex, inner: Option<MoveType>| {
+            let handle = module.struct_handle_at(*idx);
+            let module_handle = module.module_handle_at(handle.module);
+            Self::from_struct(
+                module.address_identifier_at(module_handle.address),
+                module.identifier_at(module_handle.name).as_str(),
+                module.identifier_at(handle.name).as_str(),
+                inner,
+            )
+        };
+        match token {
+            SignatureToken::Bool => MoveType::Bool,
+            SignatureToken::U8 | SignatureToken::U16 | SignatureToken::U32 => MoveType::SmallInt,
+            SignatureToken::U64 | SignatureToken::U128 | SignatureToken::U256 => MoveType::BigInt,
+            SignatureToken::Address => MoveType::Address,
+            SignatureToken::Vector(inner) if **inner == SignatureToken::U8 => MoveType::Bytes,
+            SignatureToken::Vector(inner) => {
+                MoveType::Vector(Box::new(Self::from_token(module, inner)))
+            }
+            SignatureToken::Struct(idx) => from_handle(idx, None),
+            SignatureToken::StructInstantiation(idx, tokens) => {
+                from_handle(idx, tokens.first().map(|t| Self::from_token(module, t)))
+            }
+            SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+                Self::from_token(module, inner)
+            }
+            SignatureToken::Signer | SignatureToken::TypeParameter(_) => MoveType::Other,
+        }
+    }
+
+    fn from_struct(
+        address: &AccountAddress,
+        module: &str,
+        name: &str,
+        first_type_param: Option<MoveType>,
+    ) -> Self {
+        if *address != AccountAddress::ONE {
+            return MoveType::Struct;
+        }
+        match (module, name) {
+            ("string", "String") => MoveType::String,
+            ("object", "Object") => MoveType::Object,
+            ("option", "Option") => {
+                MoveType::Option(Box::new(first_type_param.unwrap_or(MoveType::Other)))
+            }
+            _ => MoveType::Struct,
+        }
+    }
+
+    /// The names from `TS_TYPES_FILE` which an argument of this type needs, for its type
+    /// and for its encoding.
+    fn input_imports(&self) -> &'static [&'static str] {
+        match self {
+            MoveType::BigInt => &["AnyNumber"],
+            MoveType::Address | MoveType::Object => &["Address"],
+            MoveType::Bytes => &["Bytes", "bytesToHex"],
+            MoveType::Vector(inner) => inner.input_imports(),
+            _ => &[],
+        }
+    }
+
+    /// The TypeScript type of an argument of this type.
+    fn input_type(&self) -> String {
+        match self {
+            MoveType::Bool => "boolean".to_string(),
+            MoveType::SmallInt => "number".to_string(),
+            MoveType::BigInt => "AnyNumber".to_string(),
+            MoveType::Address | MoveType::Object => "Address".to_string(),
+            MoveType::Bytes => "Bytes".to_string(),
+            MoveType::Vector(inner) => format!("{}[]", inner.input_type()),
+            MoveType::String => "string".to_string(),
+            MoveType::Option(_) | MoveType::Struct | MoveType::Other => "unknown".to_string(),
+        }
+    }
+
+    /// The TypeScript type of a value of this type returned by the REST API.
+    fn output_type(&self) -> String {
+        match self {
+            MoveType::Bool => "boolean".to_string(),
+            MoveType::SmallInt => "number".to_string(),
+            MoveType::BigInt | MoveType::Address | MoveType::Bytes | MoveType::String => {
+                "string".to_string()
+            }
+            MoveType::Vector(inner) => format!("{}[]", inner.output_type()),
+            MoveType::Object => "{ inner: string }".to_string(),
+            MoveType::Option(inner) => format!("{{ vec: [] | [{}] }}", inner.output_type()),
+            MoveType::Struct => "Record<string, unknown>".to_string(),
+            MoveType::Other => "unknown".to_string(),
+        }
+    }
+
+    /// Returns the expression which converts `expr` into the JSON representation of the REST
+    /// API, or `None` if it is already in that representation.
+    fn encode(&self, expr: &str, depth: usize) -> Option<String> {
+        match self {
+            MoveType::BigInt => Some(format!("String({})", expr)),
+            MoveType::Bytes => Some(format!("bytesToHex({})", expr)),
+            MoveType::Vector(inner) => {
+                let var = format!("v{}", depth);
+                inner
+                    .encode(&var, depth + 1)
+                    .map(|encoded| format!("{}.map(({}) => {})", expr, var, encoded))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Hands out distinct TypeScript identifiers for the parameters of a function.
+#[derive(Default)]
+struct Names(BTreeSet<String>);
+
+impl Names {
+    fn fresh(&mut self, name: &str) -> String {
+        let base = identifier(&camel_case(name));
+        let mut name = base.clone();
+        let mut idx = 1;
+        while !self.0.insert(name.clone()) {
+            name = format!("{}{}", base, idx);
+            idx += 1;
+        }
+        name
+    }
+}
+
+/// Returns the module id with the address as a short hex literal, e.g. `0x1::coin`.
+fn module_name(module: &CompiledModule) -> String {
+    let module_id = module.self_id();
+    format!(
+        "{}::{}",
+        module_id.address().to_hex_literal(),
+        module_id.name()
+    )
+}
+
+fn function_id(module: &CompiledModule, name: &str) -> String {
+    format!("{}::{}", module_name(module), name)
+}
+
+/// Returns the parameter list of a function, adding the names it uses to `imports`.
+fn signature(
+    imports: &mut BTreeSet<&'static str>,
+    ty_params: &[String],
+    params: &[(String, MoveType)],
+) -> String {
+    if !ty_params.is_empty() {
+        imports.insert("TypeTag");
+    }
+    for (_, ty) in params {
+        imports.extend(ty.input_imports());
+    }
+    ty_params
+        .iter()
+        .map(|name| format!("{}: TypeTag", name))
+        .chain(
+            params
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, ty.input_type())),
+        )
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn arguments(params: &[(String, MoveType)]) -> String {
+    params
+        .iter()
+        .map(|(name, ty)| ty.encode(name, 0).unwrap_or_else(|| name.clone()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes the doc comment of the ABI as JSDoc, followed by the id of the function.
+fn write_doc(out: &mut String, doc: &str, function_id: &str) -> fmt::Result {
+    writeln!(out, "/**")?;
+    for line in doc.trim().lines() {
+        let line = line.trim_end().replace("*/", "*\\/");
+        if line.is_empty() {
+            writeln!(out, " *")?;
+        } else {
+            writeln!(out, " * {}", line)?;
+        }
+    }
+    if !doc.trim().is_empty() {
+        writeln!(out, " *")?;
+    }
+    writeln!(out, " * Calls entry function `{}`.", function_id)?;
+    writeln!(out, " */")
+}
+
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => pascal,
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Escapes names which are reserved words in TypeScript, or which clash with the names
+/// imported from `TS_TYPES_FILE` or the generated `views` function and `Views` interface.
+fn identifier(name: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "debugger",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "new",
+        "null",
+        "return",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "with",
+        "let",
+        "static",
+        "yield",
+        "await",
+        "views",
+        "Views",
+        "AnyNumber",
+        "Address",
+        "Bytes",
+        "TypeTag",
+        "EntryFunctionPayload",
+        "ViewRequest",
+        "bytesToHex",
+    ];
+    if name.is_empty() {
+        "arg".to_string()
+    } else if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framwork::{KnownAttribute, RuntimeModuleMetadataV1, APTOS_METADATA_KEY_V1};
+    use crate::types::transaction::{ArgumentABI, TypeArgumentABI};
+    use move_compiler::shared::NumericalAddress;
+    use move_compiler::Compiler;
+    use move_core_types::identifier::Identifier;
+    use move_core_types::language_storage::{ModuleId, StructTag};
+    use move_core_types::metadata::Metadata;
+
+    const SOURCES: &str = r#"
+        module 0x1::object {
+            struct Object<phantom T> has copy, drop { inner: address }
+        }
+
+        module 0xcafe::shop {
+            use 0x1::object::Object;
+
+            struct Bytes has drop {}
+            struct Item has key {}
+            struct Pair<phantom A, phantom B> has drop {}
+
+            public entry fun buy<CoinType>(
+                _buyer: &signer,
+                _item: Object<Item>,
+                _amount: u64,
+                _memo: vector<u8>,
+            ) {}
+
+            public fun price(_item: Object<Item>, _discount: u8): u64 { 0 }
+        }
+    "#;
+
+    const EXPECTED: &str = r#"// Generated by `aptos gen-bindings` for module `0xcafe::shop`, do not edit.
+
+import { Address, AnyNumber, Bytes, EntryFunctionPayload, TypeTag, ViewRequest, bytesToHex } from "./_types";
+
+/** The type tag of struct `0xcafe::shop::Bytes`. */
+export const Bytes_: TypeTag = "0xcafe::shop::Bytes";
+
+/** The type tag of struct `0xcafe::shop::Item`. */
+export const Item: TypeTag = "0xcafe::shop::Item";
+
+/** The type tag of struct `0xcafe::shop::Pair`. */
+export function Pair(t0: TypeTag, t1: TypeTag): TypeTag {
+  return `0xcafe::shop::Pair<${t0}, ${t1}>`;
+}
+
+/**
+ * Buys an item.
+ *
+ * Calls entry function `0xcafe::shop::buy`.
+ */
+export function buy(coinType: TypeTag, item: Address, amount: AnyNumber, memo: Bytes): EntryFunctionPayload {
+  return {
+    type: "entry_function_payload",
+    function: "0xcafe::shop::buy",
+    type_arguments: [coinType],
+    arguments: [item, String(amount), bytesToHex(memo)],
+  };
+}
+
+/** The result of view function `0xcafe::shop::price`. */
+export type PriceResult = [string];
+
+/** The view functions of module `0xcafe::shop`. */
+export interface Views {
+  /** Calls view function `0xcafe::shop::price`. */
+  price(arg0: Address, arg1: number): Promise<PriceResult>;
+}
+
+/** Implements `Views` on top of `view`, which sends a request to the `/view` endpoint and returns the result. */
+export function views(view: (request: ViewRequest) => Promise<unknown[]>): Views {
+  return {
+    price: (arg0: Address, arg1: number) =>
+      view({ function: "0xcafe::shop::price", type_arguments: [], arguments: [arg0, arg1] }) as Promise<PriceResult>,
+  };
+}
+"#;
+
+    /// Compiles the shop module and marks `price` as a view function in its metadata.
+    fn shop_module() -> CompiledModule {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shop.move");
+        std::fs::write(&path, SOURCES).unwrap();
+        let (_, units) = Compiler::from_files(
+            vec![path.display().to_string()],
+            vec![],
+            BTreeMap::<String, NumericalAddress>::new(),
+        )
+        .build_and_report()
+        .unwrap();
+        let mut module = units
+            .into_iter()
+            .map(|unit| {
+                CompiledModule::deserialize(&unit.into_compiled_unit().serialize(None)).unwrap()
+            })
+            .find(|module| module.self_id().name().as_str() == "shop")
+            .unwrap();
+        let metadata = RuntimeModuleMetadataV1 {
+            fun_attributes: BTreeMap::from([(
+                "price".to_string(),
+                vec![KnownAttribute::view_function()],
+            )]),
+            ..RuntimeModuleMetadataV1::default()
+        };
+        module.metadata.push(Metadata {
+            key: APTOS_METADATA_KEY_V1.clone(),
+            value: bcs::to_bytes(&metadata).unwrap(),
+        });
+        module
+    }
+
+    fn buy_abi(module_id: ModuleId) -> EntryABI {
+        let item = TypeTag::Struct(Box::new(StructTag {
+            address: AccountAddress::ONE,
+            module: Identifier::new("object").unwrap(),
+            name: Identifier::new("Object").unwrap(),
+            type_params: vec![TypeTag::Struct(Box::new(StructTag {
+                address: *module_id.address(),
+                module: module_id.name().to_owned(),
+                name: Identifier::new("Item").unwrap(),
+                type_params: vec![],
+            }))],
+        }));
+        EntryABI::EntryFunction(EntryFunctionABI::new(
+            "buy".to_string(),
+            module_id,
+            " Buys an item.\n".to_string(),
+            vec![TypeArgumentABI::new("CoinType".to_string())],
+            vec![
+                ArgumentABI::new("item".to_string(), item),
+                ArgumentABI::new("amount".to_string(), TypeTag::U64),
+                ArgumentABI::new("memo".to_string(), TypeTag::Vector(Box::new(TypeTag::U8))),
+            ],
+        ))
+    }
+
+    #[test]
+    fn generates_entry_and_view_functions() {
+        let module = shop_module();
+        let abis = vec![buy_abi(module.self_id())];
+        let files = generate_ts_bindings(&abis, &[&module]);
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            vec![TS_TYPES_FILE, "shop.ts"]
+        );
+        assert_eq!(files["shop.ts"], EXPECTED);
+    }
+
+    #[test]
+    fn escapes_reserved_and_imported_names() {
+        for name in [
+            "TypeTag",
+            "Address",
+            "Bytes",
+            "AnyNumber",
+            "views",
+            "delete",
+        ] {
+            assert_eq!(identifier(name), format!("{}_", name));
+        }
+        assert_eq!(identifier("Coin"), "Coin");
+        assert_eq!(identifier(""), "arg");
+    }
+}
@@ -1,27 +1,31 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-//use super::docgen::DocgenOptions;
+use super::docgen::DocgenOptions;
 use super::natives::code::{
     ModuleMetadata, MoveOption, PackageDep, PackageMetadata, UpgradePolicy,
 };
+use super::diagnostics::{Diagnostic, DiagnosticsCollector};
 use super::{
     extended_checks, zip_metadata, zip_metadata_str, RuntimeModuleMetadataV1, APTOS_METADATA_KEY_V1,
 };
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 //use aptos_types::account_address::AccountAddress;
 use move_core_types::account_address::AccountAddress;
 use crate::types::transaction::EntryABI;
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use codespan_reporting::diagnostic::Severity;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
-use itertools::Itertools;
 use move_binary_format::CompiledModule;
 use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
-use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_compiler::compiled_unit::{CompiledUnit, NamedCompiledModule, NamedCompiledScript};
+use move_compiler::diagnostics::{report_diagnostics_to_buffer, Diagnostics, FilesSourceText};
+use move_compiler::unit_test::{plan_builder::construct_test_plan, TestPlan};
+use move_compiler::PASS_CFGIR;
 use move_core_types::language_storage::ModuleId;
 use move_core_types::metadata::Metadata;
 use move_model::model::GlobalEnv;
+use move_package::compilation::build_plan::BuildPlan;
 use move_package::compilation::compiled_package::CompiledPackage;
 use move_package::compilation::package_layout::CompiledPackageLayout;
 use move_package::source_package::manifest_parser::{
@@ -30,8 +34,10 @@ use move_package::source_package::manifest_parser::{
 use move_package::{BuildConfig, ModelConfig};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
 use std::io::stderr;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 pub const METADATA_FILE_NAME: &str = "package-metadata.bcs";
 pub const UPGRADE_POLICY_CUSTOM_FIELD: &str = "upgrade_policy";
@@ -54,8 +60,8 @@ pub struct BuildOptions {
     pub install_dir: Option<PathBuf>,
     #[clap(skip)] // TODO: have a parser for this; there is one in the CLI buts its  downstream
     pub named_addresses: BTreeMap<String, AccountAddress>,
-//    #[clap(skip)]
-//    pub docgen_options: Option<DocgenOptions>,
+    #[clap(skip)]
+    pub docgen_options: Option<DocgenOptions>,
     #[clap(long)]
     pub skip_fetch_latest_git_deps: bool,
     #[clap(long)]
@@ -74,7 +80,7 @@ impl Default for BuildOptions {
             with_docs: false,
             install_dir: None,
             named_addresses: Default::default(),
-//            docgen_options: None,
+            docgen_options: None,
             // This is false by default, because it could accidentally pull new dependencies
             // while in a test (and cause some havoc)
             skip_fetch_latest_git_deps: false,
@@ -83,27 +89,155 @@ impl Default for BuildOptions {
     }
 }
 
+/// The artifacts included in the metadata of a built package, see `IncludedArtifactsArgs`
+/// of the CLI.
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum IncludedArtifacts {
+    None,
+    Sparse,
+    All,
+}
+
+impl Display for IncludedArtifacts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use IncludedArtifacts::*;
+        match self {
+            None => f.write_str("none"),
+            Sparse => f.write_str("sparse"),
+            All => f.write_str("all"),
+        }
+    }
+}
+
+impl FromStr for IncludedArtifacts {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use IncludedArtifacts::*;
+        match s {
+            "none" => Ok(None),
+            "sparse" => Ok(Sparse),
+            "all" => Ok(All),
+            _ => Err("unknown variant"),
+        }
+    }
+}
+
+impl IncludedArtifacts {
+    /// Returns the options to build a package with these artifacts.
+    pub fn build_options(
+        self,
+        skip_fetch_latest_git_deps: bool,
+        named_addresses: BTreeMap<String, AccountAddress>,
+        bytecode_version: u32,
+    ) -> BuildOptions {
+        use IncludedArtifacts::*;
+        match self {
+            None => BuildOptions {
+                with_srcs: false,
+                with_abis: false,
+                with_source_maps: false,
+                // Always enable error map bytecode injection
+                with_error_map: true,
+                named_addresses,
+                skip_fetch_latest_git_deps,
+                bytecode_version: Some(bytecode_version),
+                ..BuildOptions::default()
+            },
+            Sparse => BuildOptions {
+                with_srcs: true,
+                with_abis: false,
+                with_source_maps: false,
+                with_error_map: true,
+                named_addresses,
+                skip_fetch_latest_git_deps,
+                bytecode_version: Some(bytecode_version),
+                ..BuildOptions::default()
+            },
+            All => BuildOptions {
+                with_srcs: true,
+                with_abis: true,
+                with_source_maps: true,
+                with_error_map: true,
+                named_addresses,
+                skip_fetch_latest_git_deps,
+                bytecode_version: Some(bytecode_version),
+                ..BuildOptions::default()
+            },
+        }
+    }
+}
+
+/// The compiled artifacts of a package in a serializable form.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompiledPackageOutput {
+    pub name: String,
+    pub modules: Vec<CompiledUnitOutput>,
+    pub scripts: Vec<CompiledUnitOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abis: Option<Vec<EntryABI>>,
+    /// Whether the package compiled; if not, `diagnostics` contains at least one error
+    pub success: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompiledPackageOutput {
+    /// The output of a package which failed to compile.
+    pub fn failed(name: String, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            name,
+            modules: vec![],
+            scripts: vec![],
+            abis: None,
+            success: false,
+            diagnostics,
+        }
+    }
+}
+
+/// A compiled module or script; modules are named by their module id.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompiledUnitOutput {
+    pub name: String,
+    /// Hex encoded bytecode
+    pub bytecode: String,
+}
+
+/// The bytecode of a module or script of a built package, with its source map.
+pub struct CompiledUnitArtifacts {
+    pub bytecode: Vec<u8>,
+    pub is_script: bool,
+    pub source_map: SourceMap,
+    /// The path of the Move source the unit was compiled from
+    pub source_path: PathBuf,
+}
+
 /// Represents a built package.  It allows to extract `PackageMetadata`. Can also be used to
 /// just build Move code and related artifacts.
 pub struct BuiltPackage {
     options: BuildOptions,
     package_path: PathBuf,
     package: CompiledPackage,
+    diagnostics: Vec<Diagnostic>,
+    /// The runtime metadata of the modules of dependencies, which is not part of their bytecode
+    dependency_metadata: BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
 }
 
 pub(crate) fn build_model(
     package_path: &Path,
     additional_named_addresses: BTreeMap<String, AccountAddress>,
     target_filter: Option<String>,
+    all_files_as_targets: bool,
+    test_mode: bool,
 ) -> anyhow::Result<GlobalEnv> {
     let build_config = BuildConfig {
-        dev_mode: false,
+        dev_mode: test_mode,
         additional_named_addresses,
         architecture: None,
         generate_abis: false,
         generate_docs: false,
         install_dir: None,
-        test_mode: false,
+        test_mode,
         force_recompilation: false,
         fetch_deps_only: false,
         skip_fetch_latest_git_deps: true,
@@ -112,7 +246,7 @@ pub(crate) fn build_model(
         package_path,
         ModelConfig {
             target_filter,
-            all_files_as_targets: false,
+            all_files_as_targets,
         },
     )
 }
@@ -120,9 +254,27 @@ pub(crate) fn build_model(
 impl BuiltPackage {
     /// Builds the package and on success delivers a `BuiltPackage`.
     ///
-    /// This function currently reports all Move compilation errors and warnings to stdout,
-    /// and is not `Ok` if there was an error among those.
+    /// This function is not `Ok` if there was a Move compilation error or an extended check
+    /// failed, in which case the error contains all diagnostics rendered as text. Warnings
+    /// are available from `BuiltPackage::diagnostics`.
     pub fn build(package_path: PathBuf, options: BuildOptions) -> anyhow::Result<Self> {
+        let mut diagnostics = DiagnosticsCollector::new();
+        Self::build_with_diagnostics(package_path, options, &mut diagnostics).map_err(|err| {
+            if diagnostics.has_errors() {
+                anyhow!("{}\n{}", err, diagnostics)
+            } else {
+                err
+            }
+        })
+    }
+
+    /// Builds the package like `BuiltPackage::build`, reporting the diagnostics of the Move
+    /// compiler and of the extended checks into `diagnostics` instead of rendering them.
+    pub fn build_with_diagnostics(
+        package_path: PathBuf,
+        options: BuildOptions,
+        diagnostics: &mut DiagnosticsCollector,
+    ) -> anyhow::Result<Self> {
         let build_config = BuildConfig {
             dev_mode: false,
             additional_named_addresses: options.named_addresses.clone(),
@@ -136,7 +288,22 @@ impl BuiltPackage {
             skip_fetch_latest_git_deps: options.skip_fetch_latest_git_deps,
         };
         eprintln!("Compiling, may take a little while to download git dependencies...");
-        let mut package = build_config.compile_package_no_exit(&package_path, &mut stderr())?;
+        let resolution_graph =
+            build_config.resolution_graph_for_package(&package_path, &mut stderr())?;
+        let build_plan = BuildPlan::create(resolution_graph)?;
+        let mut package = build_plan.compile_with_driver(&mut stderr(), |compiler| {
+            let (files, units_res) = compiler.build()?;
+            match units_res {
+                Ok((units, warnings)) => {
+                    diagnostics.add_compiler_diagnostics(&files, warnings);
+                    Ok((files, units))
+                }
+                Err(diags) => {
+                    diagnostics.add_compiler_diagnostics(&files, diags);
+                    bail!("compilation failed")
+                }
+            }
+        })?;
 
         // Build the Move model for extra processing and run extended checks as well derive
         // runtime metadata
@@ -144,16 +311,15 @@ impl BuiltPackage {
             package_path.as_path(),
             options.named_addresses.clone(),
             None,
+            false,
+            false,
         )?;
         let runtime_metadata = extended_checks::run_extended_checks(model);
-        if model.diag_count(Severity::Warning) > 0 {
-            let mut error_writer = StandardStream::stderr(ColorChoice::Auto);
-            model.report_diag(&mut error_writer, Severity::Warning);
-            if model.has_errors() {
-                bail!("extended checks failed")
-            }
+        diagnostics.add_model_diagnostics(model, Severity::Warning);
+        if model.has_errors() {
+            bail!("extended checks failed")
         }
-        inject_runtime_metadata(
+        let dependency_metadata = inject_runtime_metadata(
             package_path
                 .join(CompiledPackageLayout::Root.path())
                 .join(package.compiled_package_info.package_name.as_str()),
@@ -162,43 +328,150 @@ impl BuiltPackage {
             options.bytecode_version,
         )?;
 
-        // If enabled generate docs.
-//        if options.with_docs {
-//            let docgen = if let Some(opts) = options.docgen_options.clone() {
-//                opts
-//            } else {
-//                DocgenOptions::default()
-//            };
-//            let dep_paths = package
-//                .deps_compiled_units
-//                .iter()
-//                .map(|(_, u)| {
-//                    u.source_path
-//                        .parent()
-//                        .unwrap()
-//                        .parent()
-//                        .unwrap()
-//                        .join("doc")
-//                        .display()
-//                        .to_string()
-//                })
-//                .unique()
-//                .collect::<Vec<_>>();
-//            docgen.run(package_path.display().to_string(), dep_paths, model)?
-//        }
+        // If enabled generate docs from the model of the extended checks. Only the modules of
+        // the package are its targets; modules of dependencies are linked to the `doc`
+        // directories of their packages.
+        if options.with_docs {
+            let dep_doc_paths = package
+                .deps_compiled_units
+                .iter()
+                .filter_map(|(_, unit)| Some(unit.source_path.parent()?.parent()?.join("doc")))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            options.docgen_options.clone().unwrap_or_default().run(
+                &package_path,
+                &dep_doc_paths,
+                model,
+            )?;
+        }
 
         Ok(Self {
             options,
             package_path,
             package,
+            diagnostics: diagnostics.diagnostics().to_vec(),
+            dependency_metadata,
         })
     }
 
+    /// Builds the package in test mode and collects the plan of its unit tests.
+    ///
+    /// Dependencies are built in test mode as well, so test-only helpers of dependencies
+    /// are available, but only the tests of the root package are part of the plan. The
+    /// extended checks run on the test build as well, and its modules get the same runtime
+    /// metadata as in a regular build, so aborts in tests can be explained.
+    pub fn build_for_test(
+        package_path: PathBuf,
+        options: BuildOptions,
+    ) -> anyhow::Result<(Self, TestPlan)> {
+        let build_config = BuildConfig {
+            dev_mode: true,
+            additional_named_addresses: options.named_addresses.clone(),
+            architecture: None,
+            generate_abis: false,
+            generate_docs: false,
+            install_dir: options.install_dir.clone(),
+            test_mode: true,
+            force_recompilation: false,
+            fetch_deps_only: false,
+            skip_fetch_latest_git_deps: options.skip_fetch_latest_git_deps,
+        };
+        let resolution_graph =
+            build_config.resolution_graph_for_package(&package_path, &mut stderr())?;
+        let root_package = resolution_graph.root_package.package.name;
+        let build_plan = BuildPlan::create(resolution_graph)?;
+
+        let mut test_plan = None;
+        let mut package = build_plan.compile_with_driver(&mut stderr(), |compiler| {
+            let (files, comments_and_compiler_res) = compiler.run::<PASS_CFGIR>()?;
+            let (_, compiler) =
+                comments_and_compiler_res.map_err(|diags| diagnostics_to_error(&files, diags))?;
+            let (mut compiler, cfgir) = compiler.into_ast();
+            let compilation_env = compiler.compilation_env();
+            let module_tests = construct_test_plan(compilation_env, Some(root_package), &cfgir);
+            let (units, _warnings) = compiler
+                .at_cfgir(cfgir)
+                .build()
+                .map_err(|diags| diagnostics_to_error(&files, diags))?;
+            test_plan = Some((module_tests, files.clone(), units.clone()));
+            Ok((files, units))
+        })?;
+        let (module_tests, files, units) =
+            test_plan.ok_or_else(|| anyhow!("no test plan was constructed"))?;
+        let test_plan = TestPlan::new(module_tests.unwrap_or_default(), files, units);
+
+        let mut diagnostics = DiagnosticsCollector::new();
+        let model = &build_model(
+            package_path.as_path(),
+            options.named_addresses.clone(),
+            None,
+            false,
+            true,
+        )?;
+        let runtime_metadata = extended_checks::run_extended_checks(model);
+        diagnostics.add_model_diagnostics(model, Severity::Warning);
+        if model.has_errors() {
+            bail!("extended checks failed\n{}", diagnostics)
+        }
+        let dependency_metadata = inject_runtime_metadata(
+            package_path
+                .join(CompiledPackageLayout::Root.path())
+                .join(package.compiled_package_info.package_name.as_str()),
+            &mut package,
+            runtime_metadata,
+            options.bytecode_version,
+        )?;
+
+        Ok((
+            Self {
+                options,
+                package_path,
+                package,
+                diagnostics: diagnostics.diagnostics().to_vec(),
+                dependency_metadata,
+            },
+            test_plan,
+        ))
+    }
+
+    /// Returns the runtime metadata of the modules of the dependencies, by module id.
+    ///
+    /// The bytecode of dependencies is left as the dependencies were built, so their error
+    /// maps are only available from here, e.g. to explain aborts in a local VM.
+    pub fn dependency_metadata(&self) -> &BTreeMap<ModuleId, RuntimeModuleMetadataV1> {
+        &self.dependency_metadata
+    }
+
     /// Returns the name of this package.
     pub fn name(&self) -> &str {
         self.package.compiled_package_info.package_name.as_str()
     }
 
+    /// Returns the digest of the package sources and manifest, as stored in `PackageMetadata`.
+    pub fn source_digest(&self) -> Option<String> {
+        self.package
+            .compiled_package_info
+            .source_digest
+            .map(|s| s.to_string())
+    }
+
+    /// Returns the named addresses of the package and its dependencies, as resolved for the
+    /// build.
+    pub fn named_addresses(&self) -> BTreeMap<String, AccountAddress> {
+        self.package
+            .compiled_package_info
+            .address_alias_instantiation
+            .iter()
+            .map(|(name, address)| (name.to_string(), *address))
+            .collect()
+    }
+
+    /// Returns the warnings reported while building the package.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     pub fn package_path(&self) -> &Path {
         self.package_path.as_path()
     }
@@ -240,6 +513,40 @@ impl BuiltPackage {
             })
     }
 
+    /// Extracts the module ids and bytecode of all modules, including the modules of
+    /// dependencies.
+    pub fn extract_all_code(&self) -> Vec<(ModuleId, Vec<u8>)> {
+        self.package
+            .all_modules()
+            .filter_map(|unit_with_source| match &unit_with_source.unit {
+                CompiledUnit::Module(NamedCompiledModule { module, .. }) => Some((
+                    module.self_id(),
+                    unit_with_source
+                        .unit
+                        .serialize(self.options.bytecode_version),
+                )),
+                CompiledUnit::Script(_) => None,
+            })
+            .collect()
+    }
+
+    /// Extracts the module ids and bytecode of the modules of all dependencies.
+    pub fn extract_dependency_code(&self) -> Vec<(ModuleId, Vec<u8>)> {
+        self.package
+            .deps_compiled_units
+            .iter()
+            .filter_map(|(_, unit_with_source)| match &unit_with_source.unit {
+                CompiledUnit::Module(NamedCompiledModule { module, .. }) => Some((
+                    module.self_id(),
+                    unit_with_source
+                        .unit
+                        .serialize(self.options.bytecode_version),
+                )),
+                CompiledUnit::Script(_) => None,
+            })
+            .collect()
+    }
+
     /// Returns the number of scripts in the package.
     pub fn script_count(&self) -> usize {
         self.package.scripts().count()
@@ -257,14 +564,89 @@ impl BuiltPackage {
             .collect()
     }
 
+    /// Returns the serialized bytecode of the script with the given name, if it exists.
+    pub fn extract_script_code_by_name(&self, name: &str) -> Option<Vec<u8>> {
+        self.package
+            .scripts()
+            .find(|unit_with_source| unit_with_source.unit.name().as_str() == name)
+            .map(|unit_with_source| {
+                unit_with_source
+                    .unit
+                    .serialize(self.options.bytecode_version)
+            })
+    }
+
+    /// Returns the bytecode and the source map of the root module or script with the given
+    /// name, if it exists.
+    pub fn extract_unit(&self, name: &str) -> Option<CompiledUnitArtifacts> {
+        self.package
+            .root_compiled_units
+            .iter()
+            .find(|unit_with_source| unit_with_source.unit.name().as_str() == name)
+            .map(|unit_with_source| {
+                let (is_script, source_map) = match &unit_with_source.unit {
+                    CompiledUnit::Module(NamedCompiledModule { source_map, .. }) => {
+                        (false, source_map)
+                    }
+                    CompiledUnit::Script(NamedCompiledScript { source_map, .. }) => {
+                        (true, source_map)
+                    }
+                };
+                CompiledUnitArtifacts {
+                    bytecode: unit_with_source
+                        .unit
+                        .serialize(self.options.bytecode_version),
+                    is_script,
+                    source_map: source_map.clone(),
+                    source_path: unit_with_source.source_path.clone(),
+                }
+            })
+    }
+
+    /// Collects the bytecode of the root modules and scripts, and the abis if available.
+    pub fn to_output(&self) -> CompiledPackageOutput {
+        let modules = self
+            .package
+            .root_modules()
+            .filter_map(|unit_with_source| match &unit_with_source.unit {
+                CompiledUnit::Module(NamedCompiledModule { module, .. }) => {
+                    Some(CompiledUnitOutput {
+                        name: module.self_id().to_string(),
+                        bytecode: hex::encode(
+                            unit_with_source
+                                .unit
+                                .serialize(self.options.bytecode_version),
+                        ),
+                    })
+                }
+                CompiledUnit::Script(_) => None,
+            })
+            .collect();
+        let scripts = self
+            .package
+            .scripts()
+            .map(|unit_with_source| CompiledUnitOutput {
+                name: unit_with_source.unit.name().to_string(),
+                bytecode: hex::encode(
+                    unit_with_source
+                        .unit
+                        .serialize(self.options.bytecode_version),
+                ),
+            })
+            .collect();
+        CompiledPackageOutput {
+            name: self.name().to_string(),
+            modules,
+            scripts,
+            abis: self.extract_abis(),
+            success: true,
+            diagnostics: self.diagnostics.clone(),
+        }
+    }
+
     /// Extracts metadata, as needed for releasing a package, from the built package.
     pub fn extract_metadata(&self) -> anyhow::Result<PackageMetadata> {
-        let source_digest = self
-            .package
-            .compiled_package_info
-            .source_digest
-            .map(|s| s.to_string())
-            .unwrap_or_default();
+        let source_digest = self.source_digest().unwrap_or_default();
         let manifest_file = self.package_path.join("Move.toml");
         let manifest = std::fs::read_to_string(&manifest_file)?;
         let custom_props = extract_custom_fields(&manifest)?;
@@ -333,6 +715,11 @@ impl BuiltPackage {
     }
 }
 
+fn diagnostics_to_error(files: &FilesSourceText, diags: Diagnostics) -> anyhow::Error {
+    let report = report_diagnostics_to_buffer(files, diags);
+    anyhow!(String::from_utf8_lossy(&report).to_string())
+}
+
 fn extract_custom_fields(toml: &str) -> anyhow::Result<BTreeMap<String, String>> {
     let manifest = parse_source_manifest(parse_move_manifest_string(toml.to_owned())?)?;
     Ok(manifest
@@ -343,16 +730,19 @@ fn extract_custom_fields(toml: &str) -> anyhow::Result<BTreeMap<String, String>>
         .collect())
 }
 
+/// Injects the runtime metadata into the modules of the package and updates their `.mv` files.
+/// The bytecode of dependencies is left as it was built; the metadata of all other modules is
+/// returned instead.
 fn inject_runtime_metadata(
     package_path: PathBuf,
     pack: &mut CompiledPackage,
-    metadata: BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+    mut metadata: BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
     bytecode_version: Option<u32>,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<BTreeMap<ModuleId, RuntimeModuleMetadataV1>> {
     for unit_with_source in pack.root_compiled_units.iter_mut() {
         match &mut unit_with_source.unit {
             CompiledUnit::Module(named_module) => {
-                if let Some(module_metadata) = metadata.get(&named_module.module.self_id()) {
+                if let Some(module_metadata) = metadata.remove(&named_module.module.self_id()) {
                     if !module_metadata.is_empty() {
                         let serialized_metadata =
                             bcs::to_bytes(&module_metadata).expect("BCS for RuntimeModuleMetadata");
@@ -376,5 +766,6 @@ fn inject_runtime_metadata(
             CompiledUnit::Script(_) => {}
         }
     }
-    Ok(())
+    metadata.retain(|_, module_metadata| !module_metadata.is_empty());
+    Ok(metadata)
 }
@@ -0,0 +1,285 @@
+use super::natives::code::UpgradePolicy;
+use move_binary_format::file_format::{AbilitySet, Visibility};
+use move_binary_format::normalized::{Function, Module, Struct};
+use move_binary_format::CompiledModule;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A rule of the upgrade policy which is broken by a new version of a package.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibilityViolation {
+    /// The module id, e.g. `0xcafe::coin`
+    pub module: String,
+    /// The struct or function which breaks the rule, if the violation is about a single item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatibilityReport {
+    pub package: String,
+    pub old_policy: String,
+    pub new_policy: String,
+    /// Whether the new version may replace the old one
+    pub compatible: bool,
+    pub violations: Vec<CompatibilityViolation>,
+}
+
+/// Checks whether the modules of a new version of a package may replace the modules of the
+/// published version, according to the upgrade policy of the published version.
+///
+/// Under the `compatible` policy, no module may be removed, and for every module:
+/// - structs may not be removed, lose abilities, change their fields or the number of their
+///   type parameters, add constraints to a type parameter or make a phantom one non-phantom;
+/// - public and entry functions may not be removed or change their signature, and public
+///   functions must stay public;
+/// - if `check_friends` is set, friend functions must keep their signature and friends may
+///   not be removed, which the Aptos framework does not require.
+///
+/// Under the `immutable` policy every upgrade is a violation. In any case the upgrade policy
+/// itself may only become stricter.
+pub fn check_upgrade(
+    package: &str,
+    old_policy: UpgradePolicy,
+    old_modules: &[CompiledModule],
+    new_policy: UpgradePolicy,
+    new_modules: &[CompiledModule],
+    check_friends: bool,
+) -> CompatibilityReport {
+    let mut checker = Checker {
+        violations: vec![],
+        check_friends,
+    };
+    if new_policy.policy < old_policy.policy {
+        checker.violations.push(CompatibilityViolation {
+            module: package.to_string(),
+            item: None,
+            message: format!(
+                "the upgrade policy cannot be weakened from `{}` to `{}`",
+                old_policy, new_policy
+            ),
+        });
+    }
+    if old_policy == UpgradePolicy::immutable() {
+        checker.violations.push(CompatibilityViolation {
+            module: package.to_string(),
+            item: None,
+            message: "the package is immutable and cannot be upgraded".to_string(),
+        });
+    } else if old_policy == UpgradePolicy::compat() {
+        let new_modules: BTreeMap<_, _> = new_modules
+            .iter()
+            .map(|module| (module.self_id(), module))
+            .collect();
+        for old_module in old_modules {
+            let id = old_module.self_id();
+            match new_modules.get(&id) {
+                Some(new_module) => checker.check_module(
+                    &id.to_string(),
+                    &Module::new(old_module),
+                    &Module::new(new_module),
+                ),
+                None => checker.violation(&id.to_string(), None, "the module was removed"),
+            }
+        }
+    }
+    CompatibilityReport {
+        package: package.to_string(),
+        old_policy: old_policy.to_string(),
+        new_policy: new_policy.to_string(),
+        compatible: checker.violations.is_empty(),
+        violations: checker.violations,
+    }
+}
+
+struct Checker {
+    violations: Vec<CompatibilityViolation>,
+    check_friends: bool,
+}
+
+impl Checker {
+    fn violation(&mut self, module: &str, item: Option<&str>, message: &str) {
+        self.violations.push(CompatibilityViolation {
+            module: module.to_string(),
+            item: item.map(str::to_string),
+            message: message.to_string(),
+        })
+    }
+
+    fn check_module(&mut self, module: &str, old: &Module, new: &Module) {
+        for (name, old_struct) in &old.structs {
+            match new.structs.get(name) {
+                Some(new_struct) => {
+                    self.check_struct(module, name.as_str(), old_struct, new_struct)
+                }
+                None => self.violation(module, Some(name.as_str()), "the struct was removed"),
+            }
+        }
+
+        for (name, old_function) in &old.exposed_functions {
+            if old_function.visibility == Visibility::Friend
+                && !old_function.is_entry
+                && !self.check_friends
+            {
+                continue;
+            }
+            match new.exposed_functions.get(name) {
+                Some(new_function) => {
+                    self.check_function(module, name.as_str(), old_function, new_function)
+                }
+                None => self.violation(module, Some(name.as_str()), "the function was removed"),
+            }
+        }
+
+        if self.check_friends {
+            for friend in old.friends.iter().filter(|id| !new.friends.contains(id)) {
+                self.violation(
+                    module,
+                    None,
+                    &format!("the friend declaration of `{}` was removed", friend),
+                );
+            }
+        }
+    }
+
+    fn check_struct(&mut self, module: &str, name: &str, old: &Struct, new: &Struct) {
+        if !old.abilities.is_subset(new.abilities) {
+            self.violation(module, Some(name), "abilities of the struct were removed");
+        }
+        if old.type_parameters.len() != new.type_parameters.len() {
+            self.violation(
+                module,
+                Some(name),
+                "the number of type parameters of the struct changed",
+            );
+        } else {
+            for (idx, (old_param, new_param)) in old
+                .type_parameters
+                .iter()
+                .zip(&new.type_parameters)
+                .enumerate()
+            {
+                if !constraints_compatible(old_param.constraints, new_param.constraints) {
+                    self.violation(
+                        module,
+                        Some(name),
+                        &format!("constraints were added to type parameter {}", idx),
+                    );
+                }
+                if old_param.is_phantom && !new_param.is_phantom {
+                    self.violation(
+                        module,
+                        Some(name),
+                        &format!("type parameter {} is no longer phantom", idx),
+                    );
+                }
+            }
+        }
+        if old.fields != new.fields {
+            self.violation(module, Some(name), "the fields of the struct changed");
+        }
+    }
+
+    fn check_function(&mut self, module: &str, name: &str, old: &Function, new: &Function) {
+        if old.visibility == Visibility::Public && new.visibility != Visibility::Public {
+            self.violation(module, Some(name), "the function is no longer public");
+        }
+        if old.is_entry && !new.is_entry {
+            self.violation(
+                module,
+                Some(name),
+                "the function is no longer an entry function",
+            );
+        }
+        if old.parameters != new.parameters {
+            self.violation(module, Some(name), "the parameters of the function changed");
+        }
+        if old.return_ != new.return_ {
+            self.violation(
+                module,
+                Some(name),
+                "the return type of the function changed",
+            );
+        }
+        if old.type_parameters.len() != new.type_parameters.len() {
+            self.violation(
+                module,
+                Some(name),
+                "the number of type parameters of the function changed",
+            );
+        } else {
+            for (idx, (old_constraints, new_constraints)) in old
+                .type_parameters
+                .iter()
+                .zip(&new.type_parameters)
+                .enumerate()
+            {
+                if !constraints_compatible(*old_constraints, *new_constraints) {
+                    self.violation(
+                        module,
+                        Some(name),
+                        &format!("constraints were added to type parameter {}", idx),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Type arguments which satisfy the old constraints must satisfy the new ones.
+fn constraints_compatible(old: AbilitySet, new: AbilitySet) -> bool {
+    new.is_subset(old)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{Ability, StructTypeParameter};
+
+    fn phantom_struct(is_phantom: bool) -> Struct {
+        Struct {
+            abilities: AbilitySet::EMPTY,
+            type_parameters: vec![StructTypeParameter {
+                constraints: AbilitySet::EMPTY,
+                is_phantom,
+            }],
+            fields: vec![],
+        }
+    }
+
+    fn check_struct(old: &Struct, new: &Struct) -> Vec<CompatibilityViolation> {
+        let mut checker = Checker {
+            violations: vec![],
+            check_friends: false,
+        };
+        checker.check_struct("0xcafe::m", "S", old, new);
+        checker.violations
+    }
+
+    #[test]
+    fn making_a_type_parameter_phantom_is_compatible() {
+        let violations = check_struct(&phantom_struct(false), &phantom_struct(true));
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn making_a_phantom_type_parameter_non_phantom_is_incompatible() {
+        let violations = check_struct(&phantom_struct(true), &phantom_struct(false));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].item.as_deref(), Some("S"));
+        assert_eq!(
+            violations[0].message,
+            "type parameter 0 is no longer phantom"
+        );
+    }
+
+    #[test]
+    fn adding_constraints_is_incompatible() {
+        let old = phantom_struct(false);
+        let mut new = phantom_struct(false);
+        new.type_parameters[0].constraints = AbilitySet::EMPTY | Ability::Copy;
+        assert_eq!(check_struct(&old, &new).len(), 1);
+        assert!(check_struct(&new, &old).is_empty());
+    }
+}
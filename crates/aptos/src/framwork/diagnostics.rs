@@ -0,0 +1,233 @@
+use codespan::Span;
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use codespan_reporting::term::termcolor::NoColor;
+use move_compiler::diagnostics::{Diagnostics, FilesSourceText};
+use move_ir_types::location::Loc as CompilerLoc;
+use move_model::model::{GlobalEnv, Loc};
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// A position in a source file. Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A source range a diagnostic refers to, together with a message for that range.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticLabel {
+    /// Whether this is the primary range of the diagnostic
+    pub primary: bool,
+    pub message: String,
+    pub file: String,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// An error or warning reported by the compiler or by the extended checks.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// One of `bug`, `error`, `warning`, `note` or `help`
+    pub severity: &'static str,
+    pub message: String,
+    /// File and range of the primary label, if there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<Position>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<Position>,
+    pub labels: Vec<DiagnosticLabel>,
+    pub notes: Vec<String>,
+}
+
+/// Collects the diagnostics of all passes of a package build.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticsCollector {
+    diagnostics: Vec<Diagnostic>,
+    has_errors: bool,
+}
+
+impl DiagnosticsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.has_errors
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn into_inner(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    /// Adds the diagnostics reported by the Move compiler.
+    pub fn add_compiler_diagnostics(&mut self, files: &FilesSourceText, diags: Diagnostics) {
+        for (severity, message, (loc, primary_message), secondary_labels, notes) in
+            diags.into_codespan_format()
+        {
+            let labels = std::iter::once(compiler_label(files, true, loc, primary_message))
+                .chain(
+                    secondary_labels
+                        .into_iter()
+                        .map(|(loc, message)| compiler_label(files, false, loc, message)),
+                )
+                .flatten()
+                .collect();
+            self.add(severity, message.to_string(), labels, notes);
+        }
+    }
+
+    /// Adds the diagnostics of the Move model with at least the given severity, which includes
+    /// the diagnostics of the extended checks.
+    pub fn add_model_diagnostics(&mut self, env: &GlobalEnv, min_severity: Severity) {
+        let mut collected = vec![];
+        // The filter sees every diagnostic of the environment. Returning false for all of them
+        // keeps the writer empty, so nothing is printed.
+        env.report_diag_with_filter(&mut NoColor::new(vec![]), |diag| {
+            if diag.severity >= min_severity {
+                let labels = diag
+                    .labels
+                    .iter()
+                    .filter_map(|label| {
+                        let loc = Loc::new(
+                            label.file_id,
+                            Span::new(label.range.start as u32, label.range.end as u32),
+                        );
+                        let end_loc = Loc::new(
+                            label.file_id,
+                            Span::new(label.range.end as u32, label.range.end as u32),
+                        );
+                        let (file, start) = env.get_file_and_location(&loc)?;
+                        let (_, end) = env.get_file_and_location(&end_loc)?;
+                        Some(DiagnosticLabel {
+                            primary: label.style == LabelStyle::Primary,
+                            message: label.message.clone(),
+                            file,
+                            start: Position {
+                                line: start.line.to_usize() + 1,
+                                column: start.column.to_usize() + 1,
+                            },
+                            end: Position {
+                                line: end.line.to_usize() + 1,
+                                column: end.column.to_usize() + 1,
+                            },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                collected.push((
+                    diag.severity,
+                    diag.message.clone(),
+                    labels,
+                    diag.notes.clone(),
+                ));
+            }
+            false
+        });
+        for (severity, message, labels, notes) in collected {
+            self.add(severity, message, labels, notes);
+        }
+    }
+
+    /// Makes all file paths relative to `base`, for paths which are located below it.
+    pub fn strip_path_prefix(&mut self, base: &Path) {
+        let strip = |file: &mut String| {
+            if let Ok(relative) = Path::new(file.as_str()).strip_prefix(base) {
+                *file = relative.display().to_string();
+            }
+        };
+        for diagnostic in &mut self.diagnostics {
+            if let Some(file) = &mut diagnostic.file {
+                strip(file);
+            }
+            for label in &mut diagnostic.labels {
+                strip(&mut label.file);
+            }
+        }
+    }
+
+    fn add(
+        &mut self,
+        severity: Severity,
+        message: String,
+        labels: Vec<DiagnosticLabel>,
+        notes: Vec<String>,
+    ) {
+        self.has_errors |= severity >= Severity::Error;
+        let primary = labels.iter().find(|label| label.primary);
+        self.diagnostics.push(Diagnostic {
+            severity: severity_str(severity),
+            file: primary.map(|label| label.file.clone()),
+            start: primary.map(|label| label.start),
+            end: primary.map(|label| label.end),
+            message,
+            labels,
+            notes,
+        })
+    }
+}
+
+impl fmt::Display for DiagnosticsCollector {
+    /// Renders one line per diagnostic, e.g. `sources/hello.move:3:5: error: unbound module`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            if let (Some(file), Some(start)) = (&diagnostic.file, &diagnostic.start) {
+                write!(f, "{}:{}:{}: ", file, start.line, start.column)?;
+            }
+            write!(f, "{}: {}", diagnostic.severity, diagnostic.message)?;
+            for label in diagnostic.labels.iter().filter(|label| label.primary) {
+                if !label.message.is_empty() {
+                    write!(f, ": {}", label.message)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn severity_str(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    }
+}
+
+fn compiler_label(
+    files: &FilesSourceText,
+    primary: bool,
+    loc: CompilerLoc,
+    message: String,
+) -> Option<DiagnosticLabel> {
+    let (file, source) = files.get(&loc.file_hash())?;
+    Some(DiagnosticLabel {
+        primary,
+        message,
+        file: file.to_string(),
+        start: offset_to_position(source, loc.start() as usize),
+        end: offset_to_position(source, loc.end() as usize),
+    })
+}
+
+/// Converts a byte offset into a line and a character based column.
+pub(crate) fn offset_to_position(source: &str, offset: usize) -> Position {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let prefix = &source[..offset];
+    let line_start = prefix.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    Position {
+        line: prefix.matches('\n').count() + 1,
+        column: prefix[line_start..].chars().count() + 1,
+    }
+}
@@ -0,0 +1,949 @@
+use super::diagnostics::{offset_to_position, Position};
+use super::module_metadata::{get_module_metadata, RuntimeModuleMetadataV1};
+use crate::vm::move_value_to_json;
+use anyhow::{bail, Context};
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::binary_views::BinaryIndexedView;
+use move_binary_format::control_flow_graph::{ControlFlowGraph, VMControlFlowGraph};
+use move_binary_format::file_format::{
+    AbilitySet, Bytecode, CompiledModule, CompiledScript, FieldHandleIndex, FunctionDefinition,
+    FunctionDefinitionIndex, FunctionHandleIndex, ModuleHandleIndex, SignatureIndex,
+    SignatureToken, StructDefinitionIndex, StructFieldInformation, StructHandleIndex, Visibility,
+};
+use move_bytecode_source_map::mapping::SourceMapping;
+use move_bytecode_source_map::source_map::SourceMap;
+use move_bytecode_source_map::utils::source_map_from_file;
+use move_command_line_common::files::{MOVE_EXTENSION, SOURCE_MAP_EXTENSION};
+use move_core_types::identifier::Identifier;
+use move_coverage::coverage_map::ExecCoverageMap;
+use move_disassembler::disassembler::{Disassembler, DisassemblerOptions};
+use move_ir_types::location::Spanned;
+use move_package::compilation::package_layout::CompiledPackageLayout;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// What to include into the disassembly.
+#[derive(Debug, Clone, Default)]
+pub struct DisassembleOptions {
+    /// Treat the bytecode as a script instead of a module
+    pub is_script: bool,
+    /// Skip printing of private functions
+    pub skip_private: bool,
+    /// Do not print the bytecode of each function
+    pub skip_code: bool,
+    /// Do not print the locals of each function
+    pub skip_locals: bool,
+    /// Do not print the basic blocks of each function
+    pub skip_basic_blocks: bool,
+}
+
+/// The disassembly of a module or script.
+#[derive(Debug, Clone, Serialize)]
+pub struct DisassemblyOutput {
+    /// The id of the module, or `script`
+    pub name: String,
+    pub disassembly: String,
+}
+
+/// The source code and the source map which are shown next to the bytecode, and the coverage
+/// of its instructions.
+#[derive(Default)]
+pub struct DisassemblyContext {
+    /// The path and the content of the source file
+    pub source: Option<(String, String)>,
+    pub source_map: Option<SourceMap>,
+    pub coverage: Option<ExecCoverageMap>,
+}
+
+impl DisassemblyContext {
+    /// Looks up the source and the source map of a bytecode file, e.g. `coin.mv`. They are
+    /// taken from `coin.move` and `coin.mvsm` next to it, or, if the file is in the build
+    /// directory of a package, from the places given by `CompiledPackageLayout`.
+    pub fn for_bytecode_file(path: &Path) -> Self {
+        let (source_path, source_map_path) = match package_layout_paths(path) {
+            Some(paths) if !path.with_extension(SOURCE_MAP_EXTENSION).exists() => paths,
+            _ => (
+                path.with_extension(MOVE_EXTENSION),
+                path.with_extension(SOURCE_MAP_EXTENSION),
+            ),
+        };
+        Self {
+            source: std::fs::read_to_string(&source_path)
+                .ok()
+                .map(|source| (source_path.display().to_string(), source)),
+            source_map: source_map_from_file(&source_map_path).ok(),
+            coverage: None,
+        }
+    }
+}
+
+/// Returns the paths of the source and the source map of a bytecode file in the build
+/// directory of a package, i.e. for `<build>/<package>/bytecode_modules/<path>.mv` the files
+/// `<build>/<package>/sources/<path>.move` and `<build>/<package>/source_maps/<path>.mvsm`.
+fn package_layout_paths(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    for ancestor in path.ancestors().skip(1) {
+        let name = Path::new(ancestor.file_name()?);
+        if name != CompiledPackageLayout::CompiledModules.path()
+            && name != CompiledPackageLayout::CompiledScripts.path()
+        {
+            continue;
+        }
+        let package_root = ancestor.parent()?;
+        let relative = path.strip_prefix(ancestor).ok()?;
+        return Some((
+            package_root
+                .join(CompiledPackageLayout::Sources.path())
+                .join(relative)
+                .with_extension(MOVE_EXTENSION),
+            package_root
+                .join(CompiledPackageLayout::SourceMaps.path())
+                .join(relative)
+                .with_extension(SOURCE_MAP_EXTENSION),
+        ));
+    }
+    None
+}
+
+/// Disassembles the bytecode of a module, or of a script if `options.is_script` is set.
+pub fn disassemble(
+    bytecode: &[u8],
+    options: &DisassembleOptions,
+    context: DisassemblyContext,
+) -> anyhow::Result<DisassemblyOutput> {
+    let mut disassembler_options = DisassemblerOptions::new();
+    disassembler_options.print_code = !options.skip_code;
+    disassembler_options.only_externally_visible = options.skip_private;
+    disassembler_options.print_basic_blocks = !options.skip_basic_blocks;
+    disassembler_options.print_locals = !options.skip_locals;
+
+    // Without a source map, all instructions are mapped to an empty location
+    let no_loc = Spanned::unsafe_no_loc(()).loc;
+    let module: CompiledModule;
+    let script: CompiledScript;
+    let (name, view) = if options.is_script {
+        script = CompiledScript::deserialize(bytecode).context("deserializing the script")?;
+        ("script".to_string(), BinaryIndexedView::Script(&script))
+    } else {
+        module = CompiledModule::deserialize(bytecode).context("deserializing the module")?;
+        (
+            module.self_id().to_string(),
+            BinaryIndexedView::Module(&module),
+        )
+    };
+
+    let mut source_mapping = match context.source_map {
+        Some(source_map) => SourceMapping::new(source_map, view),
+        None => SourceMapping::new_from_view(view, no_loc)
+            .context("building a source mapping for the bytecode")?,
+    };
+    if let Some(source) = context.source {
+        source_mapping.with_source_code(source);
+    }
+
+    let mut disassembler = Disassembler::new(source_mapping, disassembler_options);
+    if let Some(coverage) = context.coverage {
+        disassembler.add_coverage_map(coverage);
+    }
+    Ok(DisassemblyOutput {
+        name,
+        disassembly: disassembler.disassemble()?,
+    })
+}
+
+/// A module in a structured form, with the indices of its tables resolved to names.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleDisassembly {
+    pub id: String,
+    /// The version of the bytecode
+    pub version: u32,
+    pub module_handles: Vec<String>,
+    pub struct_handles: Vec<StructHandleView>,
+    pub function_handles: Vec<FunctionHandleView>,
+    pub friends: Vec<String>,
+    pub structs: Vec<StructView>,
+    pub functions: Vec<FunctionView>,
+    pub constants: Vec<ConstantView>,
+    /// The decoded `aptos::metadata_v1` section, upgraded from `aptos::metadata_v0` if needed
+    pub metadata: Option<RuntimeModuleMetadataV1>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StructHandleView {
+    pub module: String,
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub type_parameters: Vec<TypeParameterView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionHandleView {
+    pub module: String,
+    pub name: String,
+    pub type_parameters: Vec<TypeParameterView>,
+    pub parameters: Vec<String>,
+    pub return_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeParameterView {
+    pub name: String,
+    pub abilities: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_phantom: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StructView {
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub type_parameters: Vec<TypeParameterView>,
+    pub is_native: bool,
+    pub fields: Vec<FieldView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldView {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionView {
+    pub name: String,
+    /// `public`, `friend` or `private`
+    pub visibility: String,
+    pub is_entry: bool,
+    pub is_native: bool,
+    pub type_parameters: Vec<TypeParameterView>,
+    pub parameters: Vec<String>,
+    pub return_types: Vec<String>,
+    pub acquires: Vec<String>,
+    /// The locals after the parameters; empty if skipped
+    pub locals: Vec<LocalView>,
+    /// Empty if skipped
+    pub basic_blocks: Vec<BasicBlockView>,
+    /// Empty if skipped
+    pub code: Vec<InstructionView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalView {
+    /// The index of the local, counting the parameters
+    pub index: usize,
+    /// The name of the local, if there is a source map
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+/// A basic block, given by the offsets of its first and last instruction.
+#[derive(Debug, Clone, Serialize)]
+pub struct BasicBlockView {
+    pub start: u16,
+    pub end: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructionView {
+    pub offset: u16,
+    pub instruction: String,
+    /// The function, struct or field the instruction refers to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operand: Option<String>,
+    /// The location in the source, if there is a source map
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<LocationView>,
+}
+
+/// A location in the source, as byte offsets. The position of the start is only known if the
+/// source code is available.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationView {
+    pub start: u32,
+    pub end: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConstantView {
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// The value as JSON, or as hex of its BCS if it cannot be decoded
+    pub value: serde_json::Value,
+}
+
+/// Disassembles the bytecode of a module into a structured form. The source map and source
+/// of the context provide the names of the locals and the locations of the instructions;
+/// coverage is ignored.
+pub fn disassemble_module(
+    bytecode: &[u8],
+    options: &DisassembleOptions,
+    context: &DisassemblyContext,
+) -> anyhow::Result<ModuleDisassembly> {
+    if options.is_script {
+        bail!("only modules can be disassembled into a structured form")
+    }
+    let module = CompiledModule::deserialize(bytecode).context("deserializing the module")?;
+    let module = &module;
+
+    let struct_handles = module
+        .struct_handles()
+        .iter()
+        .map(|handle| StructHandleView {
+            module: module_name(module, handle.module),
+            name: module.identifier_at(handle.name).to_string(),
+            abilities: abilities(handle.abilities),
+            type_parameters: handle
+                .type_parameters
+                .iter()
+                .enumerate()
+                .map(|(idx, param)| TypeParameterView {
+                    name: format!("T{}", idx),
+                    abilities: abilities(param.constraints),
+                    is_phantom: param.is_phantom,
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    let function_handles = module
+        .function_handles()
+        .iter()
+        .map(|handle| FunctionHandleView {
+            module: module_name(module, handle.module),
+            name: module.identifier_at(handle.name).to_string(),
+            type_parameters: function_type_parameters(&handle.type_parameters),
+            parameters: signature(module, handle.parameters),
+            return_types: signature(module, handle.return_),
+        })
+        .collect();
+
+    let structs = module
+        .struct_defs()
+        .iter()
+        .map(|def| {
+            let handle = &struct_handles[def.struct_handle.0 as usize];
+            let fields = match &def.field_information {
+                StructFieldInformation::Native => vec![],
+                StructFieldInformation::Declared(fields) => fields
+                    .iter()
+                    .map(|field| FieldView {
+                        name: module.identifier_at(field.name).to_string(),
+                        type_: token_string(module, &field.signature.0),
+                    })
+                    .collect(),
+            };
+            StructView {
+                name: handle.name.clone(),
+                abilities: handle.abilities.clone(),
+                type_parameters: handle.type_parameters.clone(),
+                is_native: matches!(def.field_information, StructFieldInformation::Native),
+                fields,
+            }
+        })
+        .collect();
+
+    let functions = module
+        .function_defs()
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| !options.skip_private || def.visibility != Visibility::Private)
+        .map(|(idx, def)| {
+            function_view(
+                module,
+                FunctionDefinitionIndex(idx as u16),
+                def,
+                options,
+                context,
+            )
+        })
+        .collect();
+
+    let constants = module
+        .constant_pool()
+        .iter()
+        .map(|constant| ConstantView {
+            type_: token_string(module, &constant.type_),
+            value: match constant.deserialize_constant() {
+                Some(value) => move_value_to_json(value),
+                None => serde_json::Value::String(format!("0x{}", hex::encode(&constant.data))),
+            },
+        })
+        .collect();
+
+    Ok(ModuleDisassembly {
+        id: module.self_id().to_string(),
+        version: module.version,
+        module_handles: (0..module.module_handles().len())
+            .map(|idx| module_name(module, ModuleHandleIndex(idx as u16)))
+            .collect(),
+        struct_handles,
+        function_handles,
+        friends: module
+            .friend_decls()
+            .iter()
+            .map(|friend| {
+                format!(
+                    "{}::{}",
+                    module
+                        .address_identifier_at(friend.address)
+                        .to_hex_literal(),
+                    module.identifier_at(friend.name)
+                )
+            })
+            .collect(),
+        structs,
+        functions,
+        constants,
+        metadata: get_module_metadata(module),
+    })
+}
+
+fn function_view(
+    module: &CompiledModule,
+    def_idx: FunctionDefinitionIndex,
+    def: &FunctionDefinition,
+    options: &DisassembleOptions,
+    context: &DisassemblyContext,
+) -> FunctionView {
+    let handle = module.function_handle_at(def.function);
+    let parameters = signature(module, handle.parameters);
+    let function_source_map = context
+        .source_map
+        .as_ref()
+        .and_then(|source_map| source_map.get_function_source_map(def_idx).ok());
+
+    let mut view = FunctionView {
+        name: module.identifier_at(handle.name).to_string(),
+        visibility: format!("{:?}", def.visibility).to_lowercase(),
+        is_entry: def.is_entry,
+        is_native: def.is_native(),
+        type_parameters: function_type_parameters(&handle.type_parameters),
+        return_types: signature(module, handle.return_),
+        acquires: def
+            .acquires_global_resources
+            .iter()
+            .map(|idx| struct_name(module, module.struct_def_at(*idx).struct_handle))
+            .collect(),
+        parameters,
+        locals: vec![],
+        basic_blocks: vec![],
+        code: vec![],
+    };
+    let code = match &def.code {
+        Some(code) => code,
+        None => return view,
+    };
+
+    if !options.skip_locals {
+        view.locals = module
+            .signature_at(code.locals)
+            .0
+            .iter()
+            .enumerate()
+            .map(|(idx, token)| LocalView {
+                index: view.parameters.len() + idx,
+                name: function_source_map
+                    .and_then(|map| map.locals.get(idx))
+                    .map(|(name, _)| name.clone()),
+                type_: token_string(module, token),
+            })
+            .collect();
+    }
+    if !options.skip_basic_blocks {
+        let cfg = VMControlFlowGraph::new(&code.code);
+        view.basic_blocks = cfg
+            .blocks()
+            .into_iter()
+            .map(|block| BasicBlockView {
+                start: cfg.block_start(block),
+                end: cfg.block_end(block),
+            })
+            .collect();
+    }
+    if !options.skip_code {
+        view.code =
+            code.code
+                .iter()
+                .enumerate()
+                .map(|(offset, instruction)| {
+                    let offset = offset as u16;
+                    let location = context
+                        .source_map
+                        .as_ref()
+                        .and_then(|source_map| source_map.get_code_location(def_idx, offset).ok())
+                        .map(|loc| LocationView {
+                            start: loc.start(),
+                            end: loc.end(),
+                            position: context.source.as_ref().map(|(_, source)| {
+                                offset_to_position(source, loc.start() as usize)
+                            }),
+                        });
+                    InstructionView {
+                        offset,
+                        instruction: format!("{:?}", instruction),
+                        operand: operand(module, instruction),
+                        location,
+                    }
+                })
+                .collect();
+    }
+    view
+}
+
+/// Resolves the function, struct or field an instruction refers to.
+fn operand(module: &CompiledModule, instruction: &Bytecode) -> Option<String> {
+    let function = |idx: FunctionHandleIndex| {
+        let handle = module.function_handle_at(idx);
+        format!(
+            "{}::{}",
+            module_name(module, handle.module),
+            module.identifier_at(handle.name)
+        )
+    };
+    let struct_def =
+        |idx: StructDefinitionIndex| struct_name(module, module.struct_def_at(idx).struct_handle);
+    let field = |idx: FieldHandleIndex| {
+        let handle = module.field_handle_at(idx);
+        let def = module.struct_def_at(handle.owner);
+        let name = match &def.field_information {
+            StructFieldInformation::Declared(fields) => fields
+                .get(handle.field as usize)
+                .map(|field| module.identifier_at(field.name).to_string())
+                .unwrap_or_default(),
+            StructFieldInformation::Native => String::new(),
+        };
+        format!("{}.{}", struct_def(handle.owner), name)
+    };
+
+    Some(match instruction {
+        Bytecode::Call(idx) => function(*idx),
+        Bytecode::CallGeneric(idx) => function(module.function_instantiation_at(*idx).handle),
+        Bytecode::Pack(idx)
+        | Bytecode::Unpack(idx)
+        | Bytecode::Exists(idx)
+        | Bytecode::MoveFrom(idx)
+        | Bytecode::MoveTo(idx)
+        | Bytecode::MutBorrowGlobal(idx)
+        | Bytecode::ImmBorrowGlobal(idx) => struct_def(*idx),
+        Bytecode::PackGeneric(idx)
+        | Bytecode::UnpackGeneric(idx)
+        | Bytecode::ExistsGeneric(idx)
+        | Bytecode::MoveFromGeneric(idx)
+        | Bytecode::MoveToGeneric(idx)
+        | Bytecode::MutBorrowGlobalGeneric(idx)
+        | Bytecode::ImmBorrowGlobalGeneric(idx) => {
+            struct_def(module.struct_instantiation_at(*idx).def)
+        }
+        Bytecode::MutBorrowField(idx) | Bytecode::ImmBorrowField(idx) => field(*idx),
+        Bytecode::MutBorrowFieldGeneric(idx) | Bytecode::ImmBorrowFieldGeneric(idx) => {
+            field(module.field_instantiation_at(*idx).handle)
+        }
+        _ => return None,
+    })
+}
+
+fn module_name(module: &CompiledModule, idx: ModuleHandleIndex) -> String {
+    let handle = module.module_handle_at(idx);
+    format!(
+        "{}::{}",
+        module
+            .address_identifier_at(handle.address)
+            .to_hex_literal(),
+        module.identifier_at(handle.name)
+    )
+}
+
+fn struct_name(module: &CompiledModule, idx: StructHandleIndex) -> String {
+    let handle = module.struct_handle_at(idx);
+    format!(
+        "{}::{}",
+        module_name(module, handle.module),
+        module.identifier_at(handle.name)
+    )
+}
+
+fn signature(module: &CompiledModule, idx: SignatureIndex) -> Vec<String> {
+    module
+        .signature_at(idx)
+        .0
+        .iter()
+        .map(|token| token_string(module, token))
+        .collect()
+}
+
+fn token_string(module: &CompiledModule, token: &SignatureToken) -> String {
+    match token {
+        SignatureToken::Bool => "bool".to_string(),
+        SignatureToken::U8 => "u8".to_string(),
+        SignatureToken::U16 => "u16".to_string(),
+        SignatureToken::U32 => "u32".to_string(),
+        SignatureToken::U64 => "u64".to_string(),
+        SignatureToken::U128 => "u128".to_string(),
+        SignatureToken::U256 => "u256".to_string(),
+        SignatureToken::Address => "address".to_string(),
+        SignatureToken::Signer => "signer".to_string(),
+        SignatureToken::Vector(inner) => format!("vector<{}>", token_string(module, inner)),
+        SignatureToken::Struct(idx) => struct_name(module, *idx),
+        SignatureToken::StructInstantiation(idx, tokens) => format!(
+            "{}<{}>",
+            struct_name(module, *idx),
+            tokens
+                .iter()
+                .map(|token| token_string(module, token))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        SignatureToken::Reference(inner) => format!("&{}", token_string(module, inner)),
+        SignatureToken::MutableReference(inner) => {
+            format!("&mut {}", token_string(module, inner))
+        }
+        SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+    }
+}
+
+fn abilities(abilities: AbilitySet) -> Vec<String> {
+    abilities
+        .into_iter()
+        .map(|ability| format!("{:?}", ability).to_lowercase())
+        .collect()
+}
+
+fn function_type_parameters(type_parameters: &[AbilitySet]) -> Vec<TypeParameterView> {
+    type_parameters
+        .iter()
+        .enumerate()
+        .map(|(idx, constraints)| TypeParameterView {
+            name: format!("T{}", idx),
+            abilities: abilities(*constraints),
+            is_phantom: false,
+        })
+        .collect()
+}
+
+/// Disassembles the bytecode of a module or script into its source, annotated with the
+/// bytecode. Each line of the source is followed by the instructions which the source map
+/// locates on it, as `<function>+<offset>: <instruction>`, and, with coverage, prefixed by the
+/// number of times the instruction was executed. Instructions are attributed to the line on
+/// which their location starts.
+pub fn disassemble_annotated(
+    bytecode: &[u8],
+    options: &DisassembleOptions,
+    context: &DisassemblyContext,
+) -> anyhow::Result<String> {
+    let ((source_path, source), source_map) = match (&context.source, &context.source_map) {
+        (Some(source), Some(source_map)) => (source, source_map),
+        _ => bail!("the annotated disassembly needs the source and the source map of the bytecode"),
+    };
+
+    let (module, script) = if options.is_script {
+        let script = CompiledScript::deserialize(bytecode).context("deserializing the script")?;
+        (None, Some(script))
+    } else {
+        let module = CompiledModule::deserialize(bytecode).context("deserializing the module")?;
+        (Some(module), None)
+    };
+    let mut functions = vec![];
+    if let Some(script) = &script {
+        functions.push(("main".to_string(), FunctionDefinitionIndex(0), &script.code));
+    }
+    if let Some(module) = &module {
+        for (idx, def) in module.function_defs().iter().enumerate() {
+            if options.skip_private && def.visibility == Visibility::Private {
+                continue;
+            }
+            if let Some(code) = &def.code {
+                let name = module
+                    .identifier_at(module.function_handle_at(def.function).name)
+                    .to_string();
+                functions.push((name, FunctionDefinitionIndex(idx as u16), code));
+            }
+        }
+    }
+    let module = module.as_ref();
+
+    let line_starts = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect::<Vec<_>>();
+    // The instructions of each line, by the 0-based index of the line
+    let mut lines = BTreeMap::<usize, Vec<String>>::new();
+    let mut unlocated = vec![];
+    for (name, def_idx, code) in &functions {
+        let coverage = module.and_then(|module| function_coverage(context, module, name));
+        for (offset, instruction) in code.code.iter().enumerate() {
+            let offset = offset as u16;
+            let mut annotation = match &coverage {
+                Some(coverage) => format!(
+                    "{:>8}  ",
+                    coverage.get(&(offset as u64)).copied().unwrap_or(0)
+                ),
+                None => String::new(),
+            };
+            write!(annotation, "{}+{}: {:?}", name, offset, instruction)?;
+            if let Some(operand) = module.and_then(|module| operand(module, instruction)) {
+                write!(annotation, " {}", operand)?;
+            }
+            match source_map.get_code_location(*def_idx, offset) {
+                Ok(loc) => {
+                    let line = line_starts.partition_point(|start| *start <= loc.start() as usize);
+                    lines.entry(line - 1).or_default().push(annotation);
+                }
+                Err(_) => unlocated.push(annotation),
+            }
+        }
+    }
+
+    let mut output = String::new();
+    writeln!(output, "// {}", source_path)?;
+    for (idx, line) in source.lines().enumerate() {
+        writeln!(output, "{:>5} | {}", idx + 1, line)?;
+        for annotation in lines.remove(&idx).into_iter().flatten() {
+            writeln!(output, "      |     {}", annotation)?;
+        }
+    }
+    // Locations beyond the end of the source, if the source does not match the source map
+    unlocated.extend(lines.into_values().flatten());
+    if !unlocated.is_empty() {
+        writeln!(output, "// Instructions without a location in the source")?;
+        for annotation in unlocated {
+            writeln!(output, "      |     {}", annotation)?;
+        }
+    }
+    Ok(output)
+}
+
+/// Returns the execution counts of the instructions of a function of a module, by offset.
+fn function_coverage<'a>(
+    context: &'a DisassemblyContext,
+    module: &CompiledModule,
+    function: &str,
+) -> Option<&'a BTreeMap<u64, u64>> {
+    let id = module.self_id();
+    context
+        .coverage
+        .as_ref()?
+        .module_maps
+        .get(&(*id.address(), id.name().to_owned()))?
+        .function_maps
+        .get(&Identifier::new(function).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framwork::APTOS_METADATA_KEY_V1;
+    use move_compiler::compiled_unit::CompiledUnit;
+    use move_compiler::shared::NumericalAddress;
+    use move_compiler::Compiler;
+    use move_core_types::errmap::ErrorDescription;
+    use move_core_types::metadata::Metadata;
+
+    const SOURCE: &str = "module 0xcafe::math {
+    public fun double(x: u64): u64 {
+        x * 2
+    }
+
+    public fun answer(): u64 {
+        42
+    }
+
+    fun sum(n: u64): u64 {
+        let total = 0;
+        let i = 0;
+        while (i < n) {
+            i = i + 1;
+            total = total + i;
+        };
+        total
+    }
+}
+";
+
+    fn compile() -> (Vec<u8>, SourceMap) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("math.move");
+        std::fs::write(&path, SOURCE).unwrap();
+        let (_, mut units) = Compiler::from_files(
+            vec![path.display().to_string()],
+            vec![],
+            BTreeMap::<String, NumericalAddress>::new(),
+        )
+        .build_and_report()
+        .unwrap();
+        let unit = units.pop().unwrap().into_compiled_unit();
+        let bytecode = unit.serialize(None);
+        match unit {
+            CompiledUnit::Module(module) => (bytecode, module.source_map),
+            CompiledUnit::Script(_) => panic!("expected a module"),
+        }
+    }
+
+    /// Returns the annotations printed below the 1-based line `line` of the source.
+    fn annotations(output: &str, line: usize) -> Vec<&str> {
+        let prefix = format!("{:>5} | ", line);
+        output
+            .lines()
+            .skip_while(|l| !l.starts_with(&prefix))
+            .skip(1)
+            .take_while(|l| l.starts_with("      |     "))
+            .collect()
+    }
+
+    #[test]
+    fn annotated_disassembly_attributes_instructions_to_their_lines() {
+        let (bytecode, source_map) = compile();
+        let context = DisassemblyContext {
+            source: Some(("math.move".to_string(), SOURCE.to_string())),
+            source_map: Some(source_map),
+            coverage: None,
+        };
+        let output =
+            disassemble_annotated(&bytecode, &DisassembleOptions::default(), &context).unwrap();
+
+        assert!(output.starts_with("// math.move\n    1 | module 0xcafe::math {\n"));
+        let double = annotations(&output, 3);
+        assert!(double.iter().all(|l| l.contains("double+")), "{}", output);
+        assert!(double.iter().any(|l| l.contains("Mul")), "{}", output);
+        let answer = annotations(&output, 7);
+        assert!(answer.iter().all(|l| l.contains("answer+")), "{}", output);
+        assert!(answer.iter().any(|l| l.contains("LdU64(42)")), "{}", output);
+        assert!(annotations(&output, 1).is_empty(), "{}", output);
+        assert!(annotations(&output, 5).is_empty(), "{}", output);
+    }
+
+    #[test]
+    fn annotated_disassembly_needs_the_source_map() {
+        let (bytecode, _) = compile();
+        let context = DisassemblyContext {
+            source: Some(("math.move".to_string(), SOURCE.to_string())),
+            ..DisassemblyContext::default()
+        };
+        assert!(
+            disassemble_annotated(&bytecode, &DisassembleOptions::default(), &context).is_err()
+        );
+    }
+
+    #[test]
+    fn module_disassembly_resolves_names_and_locations() {
+        let (bytecode, source_map) = compile();
+        let mut module = CompiledModule::deserialize(&bytecode).unwrap();
+        let metadata = RuntimeModuleMetadataV1 {
+            error_map: BTreeMap::from([(
+                1,
+                ErrorDescription {
+                    code_name: "E_OVERFLOW".to_string(),
+                    code_description: "The sum does not fit".to_string(),
+                },
+            )]),
+            ..RuntimeModuleMetadataV1::default()
+        };
+        module.metadata.push(Metadata {
+            key: APTOS_METADATA_KEY_V1.clone(),
+            value: bcs::to_bytes(&metadata).unwrap(),
+        });
+        let mut bytecode = vec![];
+        module.serialize(&mut bytecode).unwrap();
+        let context = DisassemblyContext {
+            source: Some(("math.move".to_string(), SOURCE.to_string())),
+            source_map: Some(source_map),
+            coverage: None,
+        };
+
+        let output =
+            disassemble_module(&bytecode, &DisassembleOptions::default(), &context).unwrap();
+        assert!(output.id.ends_with("::math"), "{}", output.id);
+        let names = output
+            .functions
+            .iter()
+            .map(|function| function.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["double", "answer", "sum"]);
+
+        let double = &output.functions[0];
+        assert_eq!(double.visibility, "public");
+        assert_eq!(double.parameters, vec!["u64"]);
+        assert_eq!(double.return_types, vec!["u64"]);
+        assert!(double.code.iter().any(|i| i.instruction == "Mul"));
+
+        let answer = &output.functions[1];
+        assert_eq!(answer.basic_blocks.len(), 1);
+        let load = answer
+            .code
+            .iter()
+            .find(|i| i.instruction == "LdU64(42)")
+            .unwrap();
+        let location = load.location.as_ref().unwrap();
+        assert!(SOURCE[location.start as usize..location.end as usize].contains("42"));
+        assert_eq!(location.position.as_ref().unwrap().line, 7);
+
+        let sum = &output.functions[2];
+        assert_eq!(sum.visibility, "private");
+        let locals = sum
+            .locals
+            .iter()
+            .map(|local| local.name.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert!(
+            locals.iter().any(|name| name.starts_with("total")),
+            "{:?}",
+            locals
+        );
+        assert!(
+            locals.iter().any(|name| name.starts_with('i')),
+            "{:?}",
+            locals
+        );
+        assert!(sum.locals.iter().all(|local| local.index >= 1));
+        // The loop splits the code into blocks which cover all instructions in order
+        assert!(sum.basic_blocks.len() > 1);
+        assert_eq!(sum.basic_blocks[0].start, 0);
+        for blocks in sum.basic_blocks.windows(2) {
+            assert_eq!(blocks[1].start, blocks[0].end + 1);
+        }
+        assert_eq!(
+            sum.basic_blocks.last().unwrap().end as usize,
+            sum.code.len() - 1
+        );
+        for instruction in &sum.code {
+            let line = instruction
+                .location
+                .as_ref()
+                .unwrap()
+                .position
+                .as_ref()
+                .unwrap()
+                .line;
+            assert!((10..=18).contains(&line), "{:?}", instruction);
+        }
+
+        let decoded = output.metadata.unwrap();
+        assert_eq!(decoded.error_map[&1].code_name, "E_OVERFLOW");
+        assert_eq!(
+            decoded.error_map[&1].code_description,
+            "The sum does not fit"
+        );
+    }
+
+    #[test]
+    fn module_disassembly_without_source_map_or_metadata() {
+        let (bytecode, _) = compile();
+        let options = DisassembleOptions {
+            skip_private: true,
+            skip_basic_blocks: true,
+            ..DisassembleOptions::default()
+        };
+        let output =
+            disassemble_module(&bytecode, &options, &DisassemblyContext::default()).unwrap();
+
+        assert_eq!(output.functions.len(), 2);
+        for function in &output.functions {
+            assert!(function.basic_blocks.is_empty());
+            assert!(function.locals.iter().all(|local| local.name.is_none()));
+            assert!(function.code.iter().all(|i| i.location.is_none()));
+        }
+        assert!(output.metadata.is_none());
+    }
+}
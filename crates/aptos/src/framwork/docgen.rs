@@ -1,12 +1,14 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{anyhow, Context};
+use super::built_package::build_model;
+use super::DiagnosticsCollector;
+use anyhow::{bail, Context};
 use codespan_reporting::diagnostic::Severity;
-use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use move_core_types::account_address::AccountAddress;
 use move_model::model::GlobalEnv;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, clap::Parser, serde::Serialize, serde::Deserialize, Default)]
 pub struct DocgenOptions {
@@ -50,30 +52,62 @@ pub struct DocgenOptions {
 }
 
 impl DocgenOptions {
+    /// Generates the documentation of the package at `package_path` and of all its
+    /// dependencies into `<package_path>/doc`, and returns the paths of the written files.
+    ///
+    /// This builds a model of its own in which all modules are targets. Builds with
+    /// `with_docs` instead document the modules of the package from the model of the build.
+    pub fn document_package(
+        &self,
+        package_path: &Path,
+        named_addresses: BTreeMap<String, AccountAddress>,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let model = build_model(package_path, named_addresses, None, true, false)?;
+        if model.has_errors() {
+            let mut diagnostics = DiagnosticsCollector::new();
+            diagnostics.add_model_diagnostics(&model, Severity::Warning);
+            bail!("documentation generation failed\n{}", diagnostics)
+        }
+        self.run(package_path, &[], &model)
+    }
+
+    /// Generates the documentation of all target modules of `model` and writes it to
+    /// `<package_path>/doc`. Returns the paths of the written files.
+    ///
+    /// References to modules which are not targets link to their documentation in
+    /// `dep_doc_paths`, the `doc` directories of the dependencies.
     pub fn run(
         &self,
-        package_path: String,
-        doc_path: Vec<String>,
+        package_path: &Path,
+        dep_doc_paths: &[PathBuf],
         model: &GlobalEnv,
-    ) -> anyhow::Result<()> {
-        // To get relative paths right, we need to run docgen with relative paths. To this
-        // end we need to set the current directory of the process. This in turn is not thread
-        // safe, so we need to make a critical section out of the entire generation process.
-        // TODO: fix this in docgen
-        static MUTEX: Mutex<u8> = Mutex::new(0);
-        let _lock = MUTEX.lock();
-        let current_dir = std::env::current_dir()?.canonicalize()?;
-        std::env::set_current_dir(&package_path)?;
-        let output_directory = PathBuf::from("doc");
-        let doc_path = doc_path
-            .into_iter()
-            .filter_map(|s| {
-                PathBuf::from(s)
-                    .strip_prefix(&package_path)
-                    .map(|p| p.display().to_string())
-                    .ok()
-            })
-            .collect();
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let output = self.generate(package_path, dep_doc_paths, model)?;
+        let mut written = vec![];
+        for (dest, content) in output {
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&dest, content)
+                .with_context(|| format!("writing `{}`", dest.display()))?;
+            written.push(dest);
+        }
+        Ok(written)
+    }
+
+    /// Generates the documentation of all target modules of `model` without writing it.
+    /// Returns the content of each file, keyed by its path below `<package_path>/doc`.
+    ///
+    /// All paths handed to docgen are joined to `package_path` rather than being relative
+    /// to the current directory of the process, which cannot be changed reliably under WASI.
+    /// Links between the generated files are relative, so they are the same either way.
+    pub fn generate(
+        &self,
+        package_path: &Path,
+        dep_doc_paths: &[PathBuf],
+        model: &GlobalEnv,
+    ) -> anyhow::Result<BTreeMap<PathBuf, String>> {
+        let output_directory = package_path.join("doc");
         let options = move_docgen::DocgenOptions {
             section_level_start: 1,
             include_private_fun: self.include_impl,
@@ -83,35 +117,32 @@ impl DocgenOptions {
             toc_depth: 3,
             collapsed_sections: self.collapsed_sections,
             output_directory: output_directory.display().to_string(),
-            doc_path,
+            doc_path: dep_doc_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
             root_doc_templates: self
                 .landing_page_template
                 .as_ref()
-                .map(|s| vec![s.clone()])
+                .map(|s| vec![package_path.join(s).display().to_string()])
                 .unwrap_or_else(Vec::new),
-            references_file: self.references_file.clone(),
+            references_file: self
+                .references_file
+                .as_ref()
+                .map(|s| package_path.join(s).display().to_string()),
             include_dep_diagrams: self.include_dep_diagram,
             include_call_diagrams: false,
             compile_relative_to_output_dir: false,
         };
         let output = move_docgen::Docgen::new(model, &options).gen();
-        if model.diag_count(Severity::Warning) > 0 {
-            let mut error_writer = StandardStream::stderr(ColorChoice::Auto);
-            model.report_diag(&mut error_writer, Severity::Warning);
+        if model.has_errors() {
+            let mut diagnostics = DiagnosticsCollector::new();
+            diagnostics.add_model_diagnostics(model, Severity::Warning);
+            bail!("documentation generation failed\n{}", diagnostics)
         }
-        let res = if model.has_errors() {
-            Err(anyhow!("documentation generation failed"))
-        } else {
-            // Write the generated output files
-            std::fs::create_dir_all(&output_directory)?;
-            for (file_name, content) in output {
-                let dest = PathBuf::from(file_name);
-                std::fs::write(dest.as_path(), content)
-                    .with_context(|| format!("writing `{}`", dest.display()))?;
-            }
-            Ok(())
-        };
-        std::env::set_current_dir(current_dir)?;
-        res
+        Ok(output
+            .into_iter()
+            .map(|(file_name, content)| (PathBuf::from(file_name), content))
+            .collect())
     }
 }
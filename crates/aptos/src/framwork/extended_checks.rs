@@ -22,8 +22,9 @@ const LEGAC_ENTRY_FUN_ATTRIBUTE: &str = "legacy_entry_fun";
 const ERROR_PREFIX: &str = "E";
 
 /// Run the extended context checker on target modules in the environment and returns a map
-/// from module to extended runtime metadata. Any errors during context checking are reported to
-/// `env`. This is invoked after general build succeeds.
+/// from module to extended runtime metadata, which includes the error maps of all modules.
+/// Any errors during context checking are reported to `env`. This is invoked after general
+/// build succeeds.
 pub fn run_extended_checks(env: &GlobalEnv) -> BTreeMap<ModuleId, RuntimeModuleMetadataV1> {
     let mut checker = ExtendedChecker::new(env);
     checker.run();
@@ -57,8 +58,10 @@ impl<'a> ExtendedChecker<'a> {
                 self.check_init_module(module);
                 self.check_entry_functions(module);
                 self.check_and_record_view_functions(module);
-                self.build_error_map(module)
             }
+            // Dependencies were checked when their packages were built, but their error maps
+            // are needed as well to explain aborts in them.
+            self.build_error_map(module)
         }
     }
 }
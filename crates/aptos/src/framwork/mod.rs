@@ -1,14 +1,43 @@
-//pub mod docgen;
+pub mod docgen;
+mod argument_parser;
+mod bindings;
 mod built_package;
+mod compatibility;
+mod diagnostics;
+mod disassembly;
 pub mod extended_checks;
 
 mod module_metadata;
 
 pub mod natives;
+mod payload;
+mod stored_package;
+mod verification;
+mod virtual_package;
 mod zip;
 
-//mod account;
-
-pub use built_package::{BuildOptions, BuiltPackage};
-pub use zip::{zip_metadata, zip_metadata_str};
-pub use module_metadata::{KnownAttribute, RuntimeModuleMetadataV1, APTOS_METADATA_KEY_V1};
\ No newline at end of file
+pub use built_package::{
+    BuildOptions, BuiltPackage, CompiledPackageOutput, CompiledUnitArtifacts, CompiledUnitOutput,
+    IncludedArtifacts,
+};
+pub use compatibility::{check_upgrade, CompatibilityReport, CompatibilityViolation};
+pub use argument_parser::{ArgumentError, ArgumentParser};
+pub use bindings::{generate_ts_bindings, TS_TYPES_FILE};
+pub use payload::{parameters_from_abi, parameters_from_module};
+pub use diagnostics::{Diagnostic, DiagnosticLabel, DiagnosticsCollector, Position};
+pub use disassembly::{
+    disassemble, disassemble_annotated, disassemble_module, BasicBlockView, ConstantView,
+    DisassembleOptions, DisassemblyContext, DisassemblyOutput, FieldView, FunctionHandleView,
+    FunctionView, InstructionView, LocalView, LocationView, ModuleDisassembly, StructHandleView,
+    StructView, TypeParameterView,
+};
+pub use stored_package::{
+    ModuleMetadataView, PackageDepView, PackageMetadataView, PackageRegistries,
+    ReconstructedPackage, StoredPackage,
+};
+pub use verification::{verify_package, ModuleStatus, ModuleVerification, PackageVerification};
+pub use virtual_package::VirtualPackage;
+pub use zip::{unzip_metadata, unzip_metadata_str, zip_metadata, zip_metadata_str};
+pub use module_metadata::{
+    get_vm_metadata, KnownAttribute, RuntimeModuleMetadataV1, APTOS_METADATA_KEY_V1,
+};
\ No newline at end of file
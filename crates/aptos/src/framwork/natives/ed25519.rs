@@ -0,0 +1,178 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::helpers::make_module_natives;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::{InternalGas, InternalGasPerByte, NumBytes};
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+pub const ED25519_SIGNATURE_LENGTH: usize = 64;
+
+/// Checks that the bytes decompress to a point on the curve which is not of small order. As
+/// in Aptos, the encoding of the point is not required to be canonical.
+pub fn validate_public_key(bytes: &[u8]) -> bool {
+    let bytes = match <[u8; ED25519_PUBLIC_KEY_LENGTH]>::try_from(bytes) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    match CompressedEdwardsY(bytes).decompress() {
+        Some(point) => !point.is_small_order(),
+        None => false,
+    }
+}
+
+/// Verifies a signature over a message with the strict rules of RFC 8032, which reject
+/// non-canonical signatures and small order keys.
+pub fn verify_strict(signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(public_key) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    public_key.verify_strict(message, &signature).is_ok()
+}
+
+/***************************************************************************************************
+ * native fun public_key_validate_internal
+ *
+ *   gas cost: base_cost + unit_cost * key_length
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct PublicKeyValidateGasParameters {
+    pub base: InternalGas,
+    pub per_byte: InternalGasPerByte,
+}
+
+fn native_public_key_validate(
+    gas_params: &PublicKeyValidateGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 1);
+
+    let key_bytes = pop_arg!(arguments, Vec<u8>);
+    let cost = gas_params.base + gas_params.per_byte * NumBytes::new(key_bytes.len() as u64);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::bool(validate_public_key(&key_bytes))],
+    ))
+}
+
+pub fn make_native_public_key_validate(
+    gas_params: PublicKeyValidateGasParameters,
+) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_public_key_validate(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * native fun signature_verify_strict_internal
+ *
+ *   gas cost: base_cost + unit_cost * message_length
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct SignatureVerifyStrictGasParameters {
+    pub base: InternalGas,
+    pub per_byte: InternalGasPerByte,
+}
+
+fn native_signature_verify_strict(
+    gas_params: &SignatureVerifyStrictGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 3);
+
+    let msg = pop_arg!(arguments, Vec<u8>);
+    let pubkey = pop_arg!(arguments, Vec<u8>);
+    let signature = pop_arg!(arguments, Vec<u8>);
+    let cost = gas_params.base + gas_params.per_byte * NumBytes::new(msg.len() as u64);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::bool(verify_strict(&signature, &pubkey, &msg))],
+    ))
+}
+
+pub fn make_native_signature_verify_strict(
+    gas_params: SignatureVerifyStrictGasParameters,
+) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_signature_verify_strict(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub public_key_validate: PublicKeyValidateGasParameters,
+    pub signature_verify_strict: SignatureVerifyStrictGasParameters,
+}
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [
+        (
+            "public_key_validate_internal",
+            make_native_public_key_validate(gas_params.public_key_validate),
+        ),
+        (
+            "signature_verify_strict_internal",
+            make_native_signature_verify_strict(gas_params.signature_verify_strict),
+        ),
+    ];
+
+    make_module_natives(natives)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Test 1 of RFC 8032, section 7.1: the empty message.
+    pub(crate) const PUBLIC_KEY: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    pub(crate) const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                                        5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    #[test]
+    fn validates_public_keys() {
+        assert!(validate_public_key(&hex::decode(PUBLIC_KEY).unwrap()));
+        // The identity point has small order.
+        let mut identity = [0u8; ED25519_PUBLIC_KEY_LENGTH];
+        identity[0] = 1;
+        assert!(!validate_public_key(&identity));
+        assert!(!validate_public_key(&hex::decode(PUBLIC_KEY).unwrap()[1..]));
+    }
+
+    #[test]
+    fn verifies_the_rfc_8032_signature() {
+        let public_key = hex::decode(PUBLIC_KEY).unwrap();
+        let signature = hex::decode(SIGNATURE).unwrap();
+        assert!(verify_strict(&signature, &public_key, b""));
+        assert!(!verify_strict(&signature, &public_key, b"x"));
+        assert!(!verify_strict(&signature[1..], &public_key, b""));
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::helpers::make_module_natives;
+use crate::types::vm_status::StatusCode;
+use better_any::{Tid, TidAble};
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::effects::Event;
+use move_core_types::gas_algebra::{InternalGas, InternalGasPerByte, NumBytes};
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The native event context.
+///
+/// Collects the events emitted during a session as `(guid, sequence number, type, BCS data)`,
+/// in the order in which they were emitted.
+#[derive(Tid, Default)]
+pub struct NativeEventContext {
+    events: Vec<Event>,
+}
+
+impl NativeEventContext {
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn into_events(self) -> Vec<Event> {
+        self.events
+    }
+}
+
+/***************************************************************************************************
+ * native fun write_to_event_store
+ *
+ *   gas cost: base_cost + unit_cost * message_size
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct WriteToEventStoreGasParameters {
+    pub base: InternalGas,
+    pub per_byte: InternalGasPerByte,
+}
+
+fn native_write_to_event_store(
+    gas_params: &WriteToEventStoreGasParameters,
+    context: &mut NativeContext,
+    mut ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.len() == 3);
+
+    let ty = ty_args.pop().unwrap();
+    let msg = arguments.pop_back().unwrap();
+    let seq_num = pop_arg!(arguments, u64);
+    let guid = pop_arg!(arguments, Vec<u8>);
+
+    let type_tag = context.type_to_type_tag(&ty)?;
+    let layout = context.type_to_type_layout(&ty)?.ok_or_else(|| {
+        PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+            .with_message("type layout of the event cannot be computed".to_string())
+    })?;
+    let blob = msg.simple_serialize(&layout).ok_or_else(|| {
+        PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+            .with_message("the event cannot be serialized".to_string())
+    })?;
+    let cost = gas_params.base + gas_params.per_byte * NumBytes::new(blob.len() as u64);
+
+    let event_context = context.extensions_mut().get_mut::<NativeEventContext>();
+    event_context.events.push((guid, seq_num, type_tag, blob));
+
+    Ok(NativeResult::ok(cost, smallvec![]))
+}
+
+pub fn make_native_write_to_event_store(
+    gas_params: WriteToEventStoreGasParameters,
+) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_write_to_event_store(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub write_to_event_store: WriteToEventStoreGasParameters,
+}
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [(
+        "write_to_event_store",
+        make_native_write_to_event_store(gas_params.write_to_event_store),
+    )];
+
+    make_module_natives(natives)
+}
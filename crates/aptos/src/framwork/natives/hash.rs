@@ -0,0 +1,198 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::helpers::make_module_natives;
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::{InternalGas, InternalGasPerByte, NumBytes};
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use sha2::Digest;
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+/// The gas parameters of a hash function: a base cost plus a cost per hashed byte.
+#[derive(Debug, Clone)]
+pub struct HashGasParameters {
+    pub base: InternalGas,
+    pub per_byte: InternalGasPerByte,
+}
+
+impl HashGasParameters {
+    fn cost(&self, bytes: &[u8]) -> InternalGas {
+        self.base + self.per_byte * NumBytes::new(bytes.len() as u64)
+    }
+}
+
+/***************************************************************************************************
+ * native fun sip_hash
+ *
+ *   gas cost: base_cost + unit_cost * data_length
+ *
+ **************************************************************************************************/
+fn native_sip_hash(
+    gas_params: &HashGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(args.len() == 1);
+
+    let bytes = pop_arg!(args, Vec<u8>);
+    let cost = gas_params.cost(&bytes);
+
+    // SipHash of the serialized bytes
+    let mut hasher = siphasher::sip::SipHasher::new();
+    hasher.write(&bytes);
+    let hash = hasher.finish();
+
+    Ok(NativeResult::ok(cost, smallvec![Value::u64(hash)]))
+}
+
+pub fn make_native_sip_hash(gas_params: HashGasParameters) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| native_sip_hash(&gas_params, context, ty_args, args))
+}
+
+/***************************************************************************************************
+ * native fun keccak256, sha2_512_internal, sha3_512_internal, ripemd160_internal and
+ * blake2b_256_internal
+ *
+ *   gas cost: base_cost + unit_cost * data_length
+ *
+ **************************************************************************************************/
+fn native_digest(
+    gas_params: &HashGasParameters,
+    digest: fn(&[u8]) -> Vec<u8>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(args.len() == 1);
+
+    let bytes = pop_arg!(args, Vec<u8>);
+    let cost = gas_params.cost(&bytes);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::vector_u8(digest(&bytes))],
+    ))
+}
+
+fn keccak256(bytes: &[u8]) -> Vec<u8> {
+    sha3::Keccak256::digest(bytes).to_vec()
+}
+
+fn sha2_512(bytes: &[u8]) -> Vec<u8> {
+    sha2::Sha512::digest(bytes).to_vec()
+}
+
+fn sha3_512(bytes: &[u8]) -> Vec<u8> {
+    sha3::Sha3_512::digest(bytes).to_vec()
+}
+
+fn ripemd160(bytes: &[u8]) -> Vec<u8> {
+    ripemd160::Ripemd160::digest(bytes).to_vec()
+}
+
+fn blake2b_256(bytes: &[u8]) -> Vec<u8> {
+    blake2_rfc::blake2b::blake2b(32, &[], bytes)
+        .as_bytes()
+        .to_vec()
+}
+
+pub fn make_native_digest(
+    gas_params: HashGasParameters,
+    digest: fn(&[u8]) -> Vec<u8>,
+) -> NativeFunction {
+    Arc::new(move |_context, _ty_args, args| native_digest(&gas_params, digest, args))
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub sip_hash: HashGasParameters,
+    pub keccak256: HashGasParameters,
+    pub sha2_512: HashGasParameters,
+    pub sha3_512: HashGasParameters,
+    pub ripemd160: HashGasParameters,
+    pub blake2b_256: HashGasParameters,
+}
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [
+        ("sip_hash", make_native_sip_hash(gas_params.sip_hash)),
+        (
+            "keccak256",
+            make_native_digest(gas_params.keccak256, keccak256),
+        ),
+        (
+            "sha2_512_internal",
+            make_native_digest(gas_params.sha2_512, sha2_512),
+        ),
+        (
+            "sha3_512_internal",
+            make_native_digest(gas_params.sha3_512, sha3_512),
+        ),
+        (
+            "ripemd160_internal",
+            make_native_digest(gas_params.ripemd160, ripemd160),
+        ),
+        (
+            "blake2b_256_internal",
+            make_native_digest(gas_params.blake2b_256, blake2b_256),
+        ),
+    ];
+
+    make_module_natives(natives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_match_known_vectors() {
+        let vectors: [(fn(&[u8]) -> Vec<u8>, &[u8], &str); 6] = [
+            (
+                keccak256,
+                b"",
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                keccak256,
+                b"abc",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                sha2_512,
+                b"abc",
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                sha3_512,
+                b"abc",
+                "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+                 10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            ),
+            (
+                ripemd160,
+                b"abc",
+                "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc",
+            ),
+            (
+                blake2b_256,
+                b"",
+                "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+            ),
+        ];
+        for (digest, input, expected) in vectors {
+            assert_eq!(hex::encode(digest(input)), expected);
+        }
+    }
+}
@@ -1,3 +1,210 @@
+pub mod account;
 pub mod any;
 pub mod code;
-pub mod helpers;
\ No newline at end of file
+pub mod ed25519;
+pub mod event;
+pub mod hash;
+pub mod helpers;
+pub mod multi_ed25519;
+pub mod secp256k1;
+pub mod type_info;
+pub mod util;
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::gas_algebra::{InternalGas, InternalGasPerByte};
+use move_core_types::language_storage::CORE_CODE_ADDRESS;
+use move_vm_runtime::native_functions::{make_table_from_iter, NativeFunctionTable};
+
+pub mod status {
+    // Failure in parsing a struct type tag
+    pub const NFE_EXPECTED_STRUCT_TYPE_TAG: u64 = 0x1;
+    // Failure in address parsing (likely no correct length)
+    pub const NFE_UNABLE_TO_PARSE_ADDRESS: u64 = 0x2;
+}
+
+/// The gas parameters of the natives of the Aptos framework.
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub account: account::GasParameters,
+    pub code: code::GasParameters,
+    pub hash: hash::GasParameters,
+    pub ed25519: ed25519::GasParameters,
+    pub event: event::GasParameters,
+    pub multi_ed25519: multi_ed25519::GasParameters,
+    pub secp256k1: secp256k1::GasParameters,
+    pub type_info: type_info::GasParameters,
+    pub util: util::GasParameters,
+}
+
+impl GasParameters {
+    pub fn zeros() -> Self {
+        Self {
+            account: account::GasParameters {
+                create_address: account::CreateAddressGasParameters {
+                    base: InternalGas::zero(),
+                },
+                create_signer: account::CreateSignerGasParameters {
+                    base: InternalGas::zero(),
+                },
+            },
+            code: code::GasParameters {
+                request_publish: code::RequestPublishGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+            hash: hash::GasParameters {
+                sip_hash: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                keccak256: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                sha2_512: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                sha3_512: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                ripemd160: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                blake2b_256: hash::HashGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+            ed25519: ed25519::GasParameters {
+                public_key_validate: ed25519::PublicKeyValidateGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                signature_verify_strict: ed25519::SignatureVerifyStrictGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+            event: event::GasParameters {
+                write_to_event_store: event::WriteToEventStoreGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+            multi_ed25519: multi_ed25519::GasParameters {
+                public_key_validate: ed25519::PublicKeyValidateGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+                signature_verify_strict: ed25519::SignatureVerifyStrictGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+            secp256k1: secp256k1::GasParameters {
+                ecdsa_recover: secp256k1::EcdsaRecoverGasParameters {
+                    base: InternalGas::zero(),
+                },
+            },
+            type_info: type_info::GasParameters {
+                type_of: type_info::TypeOfGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte_in_str: InternalGasPerByte::zero(),
+                },
+                type_name: type_info::TypeNameGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte_in_str: InternalGasPerByte::zero(),
+                },
+            },
+            util: util::GasParameters {
+                from_bytes: util::FromBytesGasParameters {
+                    base: InternalGas::zero(),
+                    per_byte: InternalGasPerByte::zero(),
+                },
+            },
+        }
+    }
+}
+
+/// The gas parameters of all natives available to VM sessions of this crate.
+#[derive(Debug, Clone)]
+pub struct NativeGasParameters {
+    pub move_stdlib: move_stdlib::natives::GasParameters,
+    pub nursery: move_stdlib::natives::NurseryGasParameters,
+    pub table: move_table_extension::GasParameters,
+    pub aptos_framework: GasParameters,
+}
+
+impl NativeGasParameters {
+    pub fn zeros() -> Self {
+        Self {
+            move_stdlib: move_stdlib::natives::GasParameters::zeros(),
+            nursery: move_stdlib::natives::NurseryGasParameters::zeros(),
+            table: move_table_extension::GasParameters::zeros(),
+            aptos_framework: GasParameters::zeros(),
+        }
+    }
+}
+
+/// Returns the natives of the Aptos framework, keyed by the modules which declare them.
+pub fn all_natives(
+    framework_addr: AccountAddress,
+    gas_params: GasParameters,
+) -> NativeFunctionTable {
+    let mut natives = vec![];
+
+    macro_rules! add_natives_from_module {
+        ($module_name: expr, $natives: expr) => {
+            natives.extend(
+                $natives.map(|(func_name, func)| ($module_name.to_string(), func_name, func)),
+            );
+        };
+    }
+
+    add_natives_from_module!("account", account::make_all(gas_params.account.clone()));
+    add_natives_from_module!(
+        "create_signer",
+        account::make_all(gas_params.account)
+            .filter(|(func_name, _)| func_name.as_str() == "create_signer")
+    );
+    add_natives_from_module!("code", code::make_all(gas_params.code));
+    add_natives_from_module!("aptos_hash", hash::make_all(gas_params.hash));
+    add_natives_from_module!("ed25519", ed25519::make_all(gas_params.ed25519));
+    add_natives_from_module!("event", event::make_all(gas_params.event));
+    add_natives_from_module!(
+        "multi_ed25519",
+        multi_ed25519::make_all(gas_params.multi_ed25519)
+    );
+    add_natives_from_module!("secp256k1", secp256k1::make_all(gas_params.secp256k1));
+    add_natives_from_module!("type_info", type_info::make_all(gas_params.type_info));
+    add_natives_from_module!("from_bcs", util::make_all(gas_params.util.clone()));
+    add_natives_from_module!("util", util::make_all(gas_params.util));
+
+    make_table_from_iter(framework_addr, natives)
+}
+
+/// Returns the natives of the Move standard library, including the nursery and the unit test
+/// helpers, of the table extension and of the Aptos framework.
+///
+/// The table natives require a `NativeTableContext` in the extensions of the session. The
+/// event native of the nursery is replaced by the one of the Aptos framework, which requires
+/// a `NativeEventContext`.
+pub fn aptos_natives(gas_params: NativeGasParameters) -> NativeFunctionTable {
+    move_stdlib::natives::all_natives(CORE_CODE_ADDRESS, gas_params.move_stdlib)
+        .into_iter()
+        .chain(
+            move_stdlib::natives::nursery_natives(CORE_CODE_ADDRESS, gas_params.nursery)
+                .into_iter()
+                .filter(|(_, module_name, _, _)| module_name.as_str() != "event"),
+        )
+        .chain(move_table_extension::table_natives(
+            CORE_CODE_ADDRESS,
+            gas_params.table,
+        ))
+        .chain(all_natives(CORE_CODE_ADDRESS, gas_params.aptos_framework))
+        .collect()
+}
@@ -0,0 +1,235 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::ed25519::{
+    self, PublicKeyValidateGasParameters, SignatureVerifyStrictGasParameters,
+    ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH,
+};
+use super::helpers::make_module_natives;
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::NumBytes;
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The maximum number of keys of a multi-Ed25519 public key.
+pub const MAX_NUM_OF_KEYS: usize = 32;
+const BITMAP_NUM_OF_BYTES: usize = 4;
+
+/// Splits a multi-Ed25519 public key, the concatenated keys followed by the threshold byte,
+/// into its keys and threshold.
+fn parse_public_key(bytes: &[u8]) -> Option<(Vec<&[u8]>, usize)> {
+    let (threshold, keys) = bytes.split_last()?;
+    if keys.is_empty() || keys.len() % ED25519_PUBLIC_KEY_LENGTH != 0 {
+        return None;
+    }
+    let keys = keys.chunks(ED25519_PUBLIC_KEY_LENGTH).collect::<Vec<_>>();
+    let threshold = *threshold as usize;
+    if keys.len() > MAX_NUM_OF_KEYS || threshold == 0 || threshold > keys.len() {
+        return None;
+    }
+    Some((keys, threshold))
+}
+
+/// Checks the encoding of the public key and that every key is a valid Ed25519 key.
+pub fn validate_public_key(bytes: &[u8]) -> bool {
+    parse_public_key(bytes).map_or(false, |(keys, _)| {
+        keys.iter().all(|key| ed25519::validate_public_key(key))
+    })
+}
+
+/// Verifies a multi-Ed25519 signature, the concatenated signatures followed by a 4 byte
+/// bitmap of the keys which signed, against a multi-Ed25519 public key.
+pub fn verify_strict(signature: &[u8], public_key: &[u8], message: &[u8]) -> bool {
+    let (keys, threshold) = match parse_public_key(public_key) {
+        Some(parsed) => parsed,
+        None => return false,
+    };
+    if signature.len() < BITMAP_NUM_OF_BYTES {
+        return false;
+    }
+    let (signatures, bitmap) = signature.split_at(signature.len() - BITMAP_NUM_OF_BYTES);
+    if signatures.len() % ED25519_SIGNATURE_LENGTH != 0 {
+        return false;
+    }
+    let signatures = signatures
+        .chunks(ED25519_SIGNATURE_LENGTH)
+        .collect::<Vec<_>>();
+    // Bit i of the bitmap, counting from the most significant bit of the first byte, is set if
+    // key i signed. The signatures are ordered by key.
+    let signers = (0..MAX_NUM_OF_KEYS)
+        .filter(|i| bitmap[i / 8] & (0b1000_0000 >> (i % 8)) != 0)
+        .collect::<Vec<_>>();
+    if signers.len() != signatures.len()
+        || signers.len() < threshold
+        || signers.last().map_or(false, |last| *last >= keys.len())
+    {
+        return false;
+    }
+    signers
+        .iter()
+        .zip(signatures)
+        .all(|(key, signature)| ed25519::verify_strict(signature, keys[*key], message))
+}
+
+/***************************************************************************************************
+ * native fun public_key_validate_internal
+ *
+ *   gas cost: base_cost + unit_cost * key_length
+ *
+ **************************************************************************************************/
+fn native_public_key_validate(
+    gas_params: &PublicKeyValidateGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 1);
+
+    let key_bytes = pop_arg!(arguments, Vec<u8>);
+    let cost = gas_params.base + gas_params.per_byte * NumBytes::new(key_bytes.len() as u64);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::bool(validate_public_key(&key_bytes))],
+    ))
+}
+
+pub fn make_native_public_key_validate(
+    gas_params: PublicKeyValidateGasParameters,
+) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_public_key_validate(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * native fun signature_verify_strict_internal
+ *
+ *   gas cost: base_cost + unit_cost * message_length
+ *
+ **************************************************************************************************/
+fn native_signature_verify_strict(
+    gas_params: &SignatureVerifyStrictGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 3);
+
+    let msg = pop_arg!(arguments, Vec<u8>);
+    let pubkey = pop_arg!(arguments, Vec<u8>);
+    let signature = pop_arg!(arguments, Vec<u8>);
+    let cost = gas_params.base + gas_params.per_byte * NumBytes::new(msg.len() as u64);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::bool(verify_strict(&signature, &pubkey, &msg))],
+    ))
+}
+
+pub fn make_native_signature_verify_strict(
+    gas_params: SignatureVerifyStrictGasParameters,
+) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_signature_verify_strict(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+pub type GasParameters = ed25519::GasParameters;
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [
+        (
+            "public_key_validate_internal",
+            make_native_public_key_validate(gas_params.public_key_validate),
+        ),
+        (
+            "signature_verify_strict_internal",
+            make_native_signature_verify_strict(gas_params.signature_verify_strict),
+        ),
+    ];
+
+    make_module_natives(natives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framwork::natives::ed25519::tests::{PUBLIC_KEY, SIGNATURE};
+
+    fn public_key(keys: usize, threshold: u8) -> Vec<u8> {
+        let mut bytes = hex::decode(PUBLIC_KEY).unwrap().repeat(keys);
+        bytes.push(threshold);
+        bytes
+    }
+
+    fn signature(signatures: usize, bitmap: [u8; BITMAP_NUM_OF_BYTES]) -> Vec<u8> {
+        let mut bytes = hex::decode(SIGNATURE).unwrap().repeat(signatures);
+        bytes.extend(bitmap);
+        bytes
+    }
+
+    #[test]
+    fn validates_public_keys() {
+        assert!(validate_public_key(&public_key(1, 1)));
+        assert!(validate_public_key(&public_key(3, 2)));
+        assert!(!validate_public_key(&public_key(1, 0)));
+        assert!(!validate_public_key(&public_key(1, 2)));
+        assert!(!validate_public_key(&public_key(MAX_NUM_OF_KEYS + 1, 1)));
+        assert!(!validate_public_key(&public_key(1, 1)[1..]));
+    }
+
+    #[test]
+    fn verifies_signatures_of_the_keys_in_the_bitmap() {
+        assert!(verify_strict(
+            &signature(1, [0b1000_0000, 0, 0, 0]),
+            &public_key(1, 1),
+            b""
+        ));
+        assert!(verify_strict(
+            &signature(2, [0b0100_0000, 0, 0, 0b0000_0001]),
+            &public_key(MAX_NUM_OF_KEYS, 2),
+            b""
+        ));
+        assert!(!verify_strict(
+            &signature(1, [0b1000_0000, 0, 0, 0]),
+            &public_key(1, 1),
+            b"x"
+        ));
+    }
+
+    #[test]
+    fn rejects_signatures_which_do_not_match_the_bitmap() {
+        // Below the threshold
+        assert!(!verify_strict(
+            &signature(1, [0b1000_0000, 0, 0, 0]),
+            &public_key(2, 2),
+            b""
+        ));
+        // More signers than signatures
+        assert!(!verify_strict(
+            &signature(1, [0b1100_0000, 0, 0, 0]),
+            &public_key(2, 1),
+            b""
+        ));
+        // A signer without a key
+        assert!(!verify_strict(
+            &signature(1, [0b0100_0000, 0, 0, 0]),
+            &public_key(1, 1),
+            b""
+        ));
+        // No bitmap
+        assert!(!verify_strict(&[0; 3], &public_key(1, 1), b""));
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::helpers::make_module_natives;
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::InternalGas;
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Abort code when deserialization fails (0x01 == INVALID_ARGUMENT)
+const NFE_DESERIALIZE: u64 = 0x01_0001;
+
+/// Recovers the uncompressed public key, without its leading tag byte, which signed the
+/// 32 byte message hash.
+///
+/// Fails if the message, the recovery id or the signature cannot be parsed. Returns `None` if
+/// no public key can be recovered from a well-formed signature.
+pub fn recover(
+    message: &[u8],
+    recovery_id: u8,
+    signature: &[u8],
+) -> Result<Option<Vec<u8>>, libsecp256k1::Error> {
+    let message = libsecp256k1::Message::parse_slice(message)?;
+    let recovery_id = libsecp256k1::RecoveryId::parse(recovery_id)?;
+    let signature = libsecp256k1::Signature::parse_standard_slice(signature)?;
+    Ok(libsecp256k1::recover(&message, &signature, &recovery_id)
+        .ok()
+        .map(|public_key| public_key.serialize()[1..].to_vec()))
+}
+
+/***************************************************************************************************
+ * native fun ecdsa_recover_internal
+ *
+ *   gas cost: base_cost
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct EcdsaRecoverGasParameters {
+    pub base: InternalGas,
+}
+
+fn native_ecdsa_recover(
+    gas_params: &EcdsaRecoverGasParameters,
+    _context: &mut NativeContext,
+    _ty_args: Vec<Type>,
+    mut arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(_ty_args.is_empty());
+    debug_assert!(arguments.len() == 3);
+
+    let signature = pop_arg!(arguments, Vec<u8>);
+    let recovery_id = pop_arg!(arguments, u8);
+    let msg = pop_arg!(arguments, Vec<u8>);
+    let cost = gas_params.base;
+
+    Ok(match recover(&msg, recovery_id, &signature) {
+        Ok(Some(public_key)) => NativeResult::ok(
+            cost,
+            smallvec![Value::vector_u8(public_key), Value::bool(true)],
+        ),
+        Ok(None) => NativeResult::ok(
+            cost,
+            smallvec![Value::vector_u8([0u8; 0]), Value::bool(false)],
+        ),
+        Err(_) => NativeResult::err(cost, NFE_DESERIALIZE),
+    })
+}
+
+pub fn make_native_ecdsa_recover(gas_params: EcdsaRecoverGasParameters) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| {
+        native_ecdsa_recover(&gas_params, context, ty_args, args)
+    })
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub ecdsa_recover: EcdsaRecoverGasParameters,
+}
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [(
+        "ecdsa_recover_internal",
+        make_native_ecdsa_recover(gas_params.ecdsa_recover),
+    )];
+
+    make_module_natives(natives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(message: &[u8; 32]) -> ([u8; 64], u8, Vec<u8>) {
+        let secret_key = libsecp256k1::SecretKey::parse(&[0x42; 32]).unwrap();
+        let (signature, recovery_id) =
+            libsecp256k1::sign(&libsecp256k1::Message::parse(message), &secret_key);
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        (
+            signature.serialize(),
+            recovery_id.serialize(),
+            public_key.serialize()[1..].to_vec(),
+        )
+    }
+
+    #[test]
+    fn recovers_the_signing_key() {
+        let message = [0x11; 32];
+        let (signature, recovery_id, public_key) = sign(&message);
+        assert_eq!(public_key.len(), 64);
+        assert_eq!(
+            recover(&message, recovery_id, &signature).unwrap(),
+            Some(public_key.clone())
+        );
+        assert_ne!(
+            recover(&[0x22; 32], recovery_id, &signature).unwrap(),
+            Some(public_key)
+        );
+    }
+
+    #[test]
+    fn malformed_arguments_are_errors() {
+        let message = [0x11; 32];
+        let (signature, recovery_id, _) = sign(&message);
+        assert!(recover(&message[1..], recovery_id, &signature).is_err());
+        assert!(recover(&message, 4, &signature).is_err());
+        assert!(recover(&message, recovery_id, &signature[1..]).is_err());
+        assert!(recover(&message, recovery_id, &[0xff; 64]).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use super::helpers::make_module_natives;
+use itertools::Itertools;
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::gas_algebra::{InternalGas, InternalGasPerByte, NumBytes};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction};
+use move_vm_types::{
+    loaded_data::runtime_types::Type,
+    natives::function::NativeResult,
+    values::{Struct, Value},
+};
+use smallvec::smallvec;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Arc;
+
+fn type_of_internal(
+    struct_tag: &StructTag,
+) -> Result<smallvec::SmallVec<[Value; 1]>, std::fmt::Error> {
+    let mut name = struct_tag.name.to_string();
+    if !struct_tag.type_params.is_empty() {
+        write!(
+            name,
+            "<{}>",
+            struct_tag
+                .type_params
+                .iter()
+                .map(|ty| ty.to_string())
+                .join(", ")
+        )?;
+    }
+
+    let struct_value = Struct::pack(vec![
+        Value::address(struct_tag.address),
+        Value::vector_u8(struct_tag.module.as_bytes().to_vec()),
+        Value::vector_u8(name.as_bytes().to_vec()),
+    ]);
+    Ok(smallvec![Value::struct_(struct_value)])
+}
+
+/***************************************************************************************************
+ * native fun type_of
+ *
+ *   Returns the structs Module Address, Module Name and the Structs Name.
+ *
+ *   gas cost: base_cost + unit_cost * type_size
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct TypeOfGasParameters {
+    pub base: InternalGas,
+    pub per_byte_in_str: InternalGasPerByte,
+}
+
+fn native_type_of(
+    gas_params: &TypeOfGasParameters,
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.is_empty());
+
+    let type_tag = context.type_to_type_tag(&ty_args[0])?;
+
+    if let TypeTag::Struct(struct_tag) = type_tag {
+        let type_tag_str = struct_tag.to_string();
+        // Ideally, we would charge *before* the `type_to_type_tag()` and `type_tag.to_string()` calls above.
+        // But there are other limits in place that prevent this native from being called with too much work.
+        let cost =
+            gas_params.base + gas_params.per_byte_in_str * NumBytes::new(type_tag_str.len() as u64);
+
+        match type_of_internal(&struct_tag) {
+            Ok(values) => Ok(NativeResult::ok(cost, values)),
+            Err(_) => Ok(NativeResult::err(
+                cost,
+                super::status::NFE_EXPECTED_STRUCT_TYPE_TAG,
+            )),
+        }
+    } else {
+        Ok(NativeResult::err(
+            gas_params.base,
+            super::status::NFE_EXPECTED_STRUCT_TYPE_TAG,
+        ))
+    }
+}
+
+pub fn make_native_type_of(gas_params: TypeOfGasParameters) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| native_type_of(&gas_params, context, ty_args, args))
+}
+
+/***************************************************************************************************
+ * native fun type_name
+ *
+ *   Returns a string representing the TypeTag of the parameter.
+ *
+ *   gas cost: base_cost + unit_cost * type_size
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct TypeNameGasParameters {
+    pub base: InternalGas,
+    pub per_byte_in_str: InternalGasPerByte,
+}
+
+fn native_type_name(
+    gas_params: &TypeNameGasParameters,
+    context: &mut NativeContext,
+    ty_args: Vec<Type>,
+    arguments: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(arguments.is_empty());
+
+    let type_tag = context.type_to_type_tag(&ty_args[0])?;
+    let type_name = type_tag.to_string();
+
+    // TODO: Ideally, we would charge *before* the `type_to_type_tag()` and `type_tag.to_string()` calls above.
+    let cost = gas_params.base + gas_params.per_byte_in_str * NumBytes::new(type_name.len() as u64);
+
+    Ok(NativeResult::ok(
+        cost,
+        smallvec![Value::struct_(Struct::pack(vec![Value::vector_u8(
+            type_name.as_bytes().to_vec()
+        )]))],
+    ))
+}
+
+pub fn make_native_type_name(gas_params: TypeNameGasParameters) -> NativeFunction {
+    Arc::new(move |context, ty_args, args| native_type_name(&gas_params, context, ty_args, args))
+}
+
+/***************************************************************************************************
+ * module
+ *
+ **************************************************************************************************/
+#[derive(Debug, Clone)]
+pub struct GasParameters {
+    pub type_of: TypeOfGasParameters,
+    pub type_name: TypeNameGasParameters,
+}
+
+pub fn make_all(gas_params: GasParameters) -> impl Iterator<Item = (String, NativeFunction)> {
+    let natives = [
+        ("type_of", make_native_type_of(gas_params.type_of)),
+        ("type_name", make_native_type_name(gas_params.type_name)),
+    ];
+
+    make_module_natives(natives)
+}
@@ -0,0 +1,138 @@
+use crate::types::transaction::EntryABI;
+use anyhow::{anyhow, bail, ensure};
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::file_format::SignatureToken;
+use move_binary_format::CompiledModule;
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{ModuleId, StructTag, TypeTag};
+
+/// Returns the types of the arguments of an entry function, as described by its ABI, with
+/// the type parameters instantiated by `ty_args`. Signers are not part of the ABI.
+pub fn parameters_from_abi(
+    abis: &[EntryABI],
+    module_id: &ModuleId,
+    function: &IdentStr,
+    ty_args: &[TypeTag],
+) -> Option<anyhow::Result<Vec<TypeTag>>> {
+    let abi = abis.iter().find_map(|abi| match abi {
+        EntryABI::EntryFunction(abi)
+            if abi.module_name() == module_id && abi.name() == function.as_str() =>
+        {
+            Some(abi)
+        }
+        _ => None,
+    })?;
+    Some(check_type_args(abi.ty_args().len(), ty_args).map(|()| {
+        abi.args()
+            .iter()
+            .map(|arg| arg.type_tag().clone())
+            .collect()
+    }))
+}
+
+/// Returns the types of the arguments of an entry function, as declared in the compiled
+/// module, with the type parameters instantiated by `ty_args` and the leading signers
+/// skipped.
+pub fn parameters_from_module(
+    module: &CompiledModule,
+    function: &IdentStr,
+    ty_args: &[TypeTag],
+) -> anyhow::Result<Vec<TypeTag>> {
+    let def = module
+        .function_defs()
+        .iter()
+        .find(|def| module.identifier_at(module.function_handle_at(def.function).name) == function)
+        .ok_or_else(|| {
+            anyhow!(
+                "function `{}::{}` does not exist",
+                module.self_id(),
+                function
+            )
+        })?;
+    ensure!(
+        def.is_entry,
+        "function `{}::{}` is not an entry function",
+        module.self_id(),
+        function
+    );
+    let handle = module.function_handle_at(def.function);
+    check_type_args(handle.type_parameters.len(), ty_args)?;
+    module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .skip_while(|token| is_signer(token))
+        .map(|token| {
+            signature_to_type_tag(module, token, ty_args).ok_or_else(|| {
+                anyhow!(
+                    "function `{}::{}` takes an argument of an unsupported type",
+                    module.self_id(),
+                    function
+                )
+            })
+        })
+        .collect()
+}
+
+fn check_type_args(expected: usize, ty_args: &[TypeTag]) -> anyhow::Result<()> {
+    if expected != ty_args.len() {
+        bail!(
+            "expected {} type arguments, but {} were given",
+            expected,
+            ty_args.len()
+        );
+    }
+    Ok(())
+}
+
+fn is_signer(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) => matches!(**inner, SignatureToken::Signer),
+        _ => false,
+    }
+}
+
+/// Converts a parameter type into a type tag, or returns `None` for references and signers,
+/// which cannot be passed in a transaction.
+fn signature_to_type_tag(
+    module: &CompiledModule,
+    token: &SignatureToken,
+    ty_args: &[TypeTag],
+) -> Option<TypeTag> {
+    let struct_tag = |idx, type_params| {
+        let handle = module.struct_handle_at(idx);
+        let module_handle = module.module_handle_at(handle.module);
+        TypeTag::Struct(Box::new(StructTag {
+            address: *module.address_identifier_at(module_handle.address),
+            module: module.identifier_at(module_handle.name).to_owned(),
+            name: module.identifier_at(handle.name).to_owned(),
+            type_params,
+        }))
+    };
+    Some(match token {
+        SignatureToken::Bool => TypeTag::Bool,
+        SignatureToken::U8 => TypeTag::U8,
+        SignatureToken::U16 => TypeTag::U16,
+        SignatureToken::U32 => TypeTag::U32,
+        SignatureToken::U64 => TypeTag::U64,
+        SignatureToken::U128 => TypeTag::U128,
+        SignatureToken::U256 => TypeTag::U256,
+        SignatureToken::Address => TypeTag::Address,
+        SignatureToken::Vector(inner) => {
+            TypeTag::Vector(Box::new(signature_to_type_tag(module, inner, ty_args)?))
+        }
+        SignatureToken::Struct(idx) => struct_tag(*idx, vec![]),
+        SignatureToken::StructInstantiation(idx, tokens) => struct_tag(
+            *idx,
+            tokens
+                .iter()
+                .map(|token| signature_to_type_tag(module, token, ty_args))
+                .collect::<Option<Vec<_>>>()?,
+        ),
+        SignatureToken::TypeParameter(idx) => ty_args.get(*idx as usize)?.clone(),
+        SignatureToken::Signer
+        | SignatureToken::Reference(_)
+        | SignatureToken::MutableReference(_) => return None,
+    })
+}
@@ -0,0 +1,469 @@
+use super::natives::code::{ModuleMetadata, PackageDep, PackageMetadata, PackageRegistry};
+use super::{unzip_metadata, unzip_metadata_str};
+use anyhow::{anyhow, bail, Context};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// A package as it is stored on chain, i.e. its `PackageMetadata`.
+///
+/// The manifest, sources and source maps in the metadata are gzip compressed; empty ones
+/// were not included when the package was built.
+#[derive(Debug, Clone)]
+pub struct StoredPackage {
+    metadata: PackageMetadata,
+}
+
+/// A JSON friendly view of `PackageMetadata` with all artifacts decompressed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageMetadataView {
+    pub name: String,
+    pub upgrade_policy: String,
+    pub upgrade_number: u64,
+    pub source_digest: String,
+    /// The content of `Move.toml`
+    pub manifest: String,
+    pub modules: Vec<ModuleMetadataView>,
+    pub deps: Vec<PackageDepView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleMetadataView {
+    pub name: String,
+    /// The Move source, if it was included
+    pub source: Option<String>,
+    /// The hex encoded BCS of the source map, if it was included
+    pub source_map: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageDepView {
+    pub account: String,
+    pub package_name: String,
+}
+
+impl StoredPackage {
+    pub fn new(metadata: PackageMetadata) -> Self {
+        Self { metadata }
+    }
+
+    /// Decodes the BCS of a `PackageMetadata`, as written to `package-metadata.bcs`.
+    pub fn from_bcs(bytes: &[u8]) -> anyhow::Result<Self> {
+        let metadata = bcs::from_bytes(bytes).context("decoding PackageMetadata")?;
+        Ok(Self::new(metadata))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    pub fn metadata(&self) -> &PackageMetadata {
+        &self.metadata
+    }
+
+    /// Returns the content of `Move.toml`.
+    pub fn manifest(&self) -> anyhow::Result<String> {
+        unzip_metadata_str(&self.metadata.manifest).context("decompressing the manifest")
+    }
+
+    /// Returns the metadata of the module with the given name, if it is part of the package.
+    pub fn module(&self, name: &str) -> Option<&ModuleMetadata> {
+        self.metadata
+            .modules
+            .iter()
+            .find(|module| module.name == name)
+    }
+
+    /// Returns the Move source of a module, if it was included.
+    pub fn module_source(module: &ModuleMetadata) -> anyhow::Result<Option<String>> {
+        if module.source.is_empty() {
+            return Ok(None);
+        }
+        unzip_metadata_str(&module.source)
+            .with_context(|| format!("decompressing the source of `{}`", module.name))
+            .map(Some)
+    }
+
+    /// Returns the source map of a module, if it was included.
+    pub fn module_source_map(module: &ModuleMetadata) -> anyhow::Result<Option<SourceMap>> {
+        if module.source_map.is_empty() {
+            return Ok(None);
+        }
+        let bytes = unzip_metadata(&module.source_map)
+            .with_context(|| format!("decompressing the source map of `{}`", module.name))?;
+        bcs::from_bytes(&bytes)
+            .with_context(|| format!("decoding the source map of `{}`", module.name))
+            .map(Some)
+    }
+
+    pub fn to_view(&self) -> anyhow::Result<PackageMetadataView> {
+        let modules = self
+            .metadata
+            .modules
+            .iter()
+            .map(|module| {
+                let source_map = if module.source_map.is_empty() {
+                    None
+                } else {
+                    let bytes = unzip_metadata(&module.source_map).with_context(|| {
+                        format!("decompressing the source map of `{}`", module.name)
+                    })?;
+                    Some(hex::encode(bytes))
+                };
+                Ok(ModuleMetadataView {
+                    name: module.name.clone(),
+                    source: Self::module_source(module)?,
+                    source_map,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(PackageMetadataView {
+            name: self.metadata.name.clone(),
+            upgrade_policy: self.metadata.upgrade_policy.to_string(),
+            upgrade_number: self.metadata.upgrade_number,
+            source_digest: self.metadata.source_digest.clone(),
+            manifest: self.manifest()?,
+            modules,
+            deps: self
+                .metadata
+                .deps
+                .iter()
+                .map(|dep| PackageDepView {
+                    account: dep.account.to_hex_literal(),
+                    package_name: dep.package_name.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Writes the manifest to `<dir>/Move.toml` and the source of every module which has one
+    /// to `<dir>/sources/<module>.move`. Returns the paths of the written files.
+    pub fn save_sources(&self, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        self.save_sources_with_manifest(dir, &self.manifest()?)
+    }
+
+    /// Like `save_sources`, but writes the given manifest instead of the stored one.
+    ///
+    /// Module names come from untrusted metadata, so every one of them must be a valid Move
+    /// identifier before any file is written.
+    pub fn save_sources_with_manifest(
+        &self,
+        dir: &Path,
+        manifest: &str,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        for module in &self.metadata.modules {
+            ensure_identifier("module", &module.name)?;
+        }
+        let sources_dir = dir.join("sources");
+        std::fs::create_dir_all(&sources_dir)
+            .with_context(|| format!("creating `{}`", sources_dir.display()))?;
+        let manifest_path = dir.join("Move.toml");
+        std::fs::write(&manifest_path, manifest)
+            .with_context(|| format!("writing `{}`", manifest_path.display()))?;
+        let mut written = vec![manifest_path];
+        for module in &self.metadata.modules {
+            if let Some(source) = Self::module_source(module)? {
+                let path = sources_dir.join(&module.name).with_extension("move");
+                std::fs::write(&path, source)
+                    .with_context(|| format!("writing `{}`", path.display()))?;
+                written.push(path);
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Fails unless `name` is a valid Move identifier, which also rules out anything that could
+/// escape the directory it is joined to.
+fn ensure_identifier(kind: &str, name: &str) -> anyhow::Result<()> {
+    if !Identifier::is_valid(name) {
+        bail!("{} name `{}` is not a valid Move identifier", kind, name)
+    }
+    Ok(())
+}
+
+/// Fails if the package name could not be used as a directory name: it must not be empty,
+/// `.` or `..`, and must not contain a path separator.
+pub(crate) fn ensure_package_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        bail!("package name `{}` cannot be used as a directory name", name)
+    }
+    Ok(())
+}
+
+/// The packages published under a set of accounts, as found in their `PackageRegistry`.
+#[derive(Debug, Clone, Default)]
+pub struct PackageRegistries {
+    registries: BTreeMap<AccountAddress, PackageRegistry>,
+}
+
+/// A package written to disk by `PackageRegistries::reconstruct`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconstructedPackage {
+    pub account: String,
+    pub name: String,
+    pub path: String,
+}
+
+impl PackageRegistries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_registry(&mut self, account: AccountAddress, registry: PackageRegistry) {
+        self.registries.insert(account, registry);
+    }
+
+    /// Decodes the BCS of the `PackageRegistry` resource of `account` and adds it.
+    pub fn add_registry_bcs(
+        &mut self,
+        account: AccountAddress,
+        bytes: &[u8],
+    ) -> anyhow::Result<()> {
+        let registry = bcs::from_bytes(bytes)
+            .with_context(|| format!("decoding the PackageRegistry of {}", account))?;
+        self.add_registry(account, registry);
+        Ok(())
+    }
+
+    /// Returns the package with the given name published under `account`.
+    pub fn get(&self, account: AccountAddress, name: &str) -> Option<StoredPackage> {
+        self.registries
+            .get(&account)?
+            .packages
+            .iter()
+            .find(|package| package.name == name)
+            .map(|metadata| StoredPackage::new(metadata.clone()))
+    }
+
+    /// Writes the source package `name` published under `account` to `<output_dir>/<name>`,
+    /// and every package it depends on next to it.
+    ///
+    /// The dependencies in each `Move.toml` are rewritten to local paths, so the packages can
+    /// be compiled without network access. All packages must have been published with
+    /// sources, and the registries of all accounts involved must have been added. Returns the
+    /// written packages, starting with the requested one. Every package name is used as a
+    /// directory name, so it must not contain path separators or be `.` or `..`.
+    pub fn reconstruct(
+        &self,
+        account: AccountAddress,
+        name: &str,
+        output_dir: &Path,
+    ) -> anyhow::Result<Vec<ReconstructedPackage>> {
+        let mut written: BTreeMap<String, AccountAddress> = BTreeMap::new();
+        let mut result = vec![];
+        let mut queue = VecDeque::from([(account, name.to_string())]);
+        while let Some((account, name)) = queue.pop_front() {
+            ensure_package_name(&name)?;
+            match written.get(&name) {
+                Some(other) if *other == account => continue,
+                Some(other) => bail!(
+                    "packages `{}` of {} and {} cannot be reconstructed side by side",
+                    name,
+                    other,
+                    account
+                ),
+                None => {}
+            }
+            let package = self.get(account, &name).ok_or_else(|| {
+                anyhow!(
+                    "package `{}` of {} is not part of the registries",
+                    name,
+                    account
+                )
+            })?;
+            if let Some(module) = package
+                .metadata()
+                .modules
+                .iter()
+                .find(|module| module.source.is_empty())
+            {
+                bail!(
+                    "package `{}` was published without the source of module `{}`",
+                    name,
+                    module.name
+                )
+            }
+            let manifest = rewrite_dependencies(&package.manifest()?, &package.metadata().deps)
+                .with_context(|| format!("rewriting the manifest of `{}`", name))?;
+            let dir = output_dir.join(&name);
+            package.save_sources_with_manifest(&dir, &manifest)?;
+
+            queue.extend(
+                package
+                    .metadata()
+                    .deps
+                    .iter()
+                    .map(|dep| (dep.account, dep.package_name.clone())),
+            );
+            written.insert(name.clone(), account);
+            result.push(ReconstructedPackage {
+                account: account.to_hex_literal(),
+                name,
+                path: dir.display().to_string(),
+            });
+        }
+        Ok(result)
+    }
+}
+
+/// Points every dependency of a manifest to a sibling directory named after the package.
+/// Dev dependencies are dropped, as they are not part of the published package.
+fn rewrite_dependencies(manifest: &str, deps: &[PackageDep]) -> anyhow::Result<String> {
+    let mut manifest: toml::Value = manifest.parse()?;
+    let table = manifest
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("the manifest is not a table"))?;
+    table.remove("dev-dependencies");
+    if let Some(dependencies) = table
+        .get_mut("dependencies")
+        .and_then(|dependencies| dependencies.as_table_mut())
+    {
+        for (name, dependency) in dependencies.iter_mut() {
+            ensure_package_name(name)?;
+            if !deps.iter().any(|dep| &dep.package_name == name) {
+                bail!("dependency `{}` is not part of the package metadata", name)
+            }
+            let mut local = toml::value::Table::new();
+            local.insert(
+                "local".to_string(),
+                toml::Value::String(format!("../{}", name)),
+            );
+            if let Some(addr_subst) = dependency.get("addr_subst") {
+                local.insert("addr_subst".to_string(), addr_subst.clone());
+            }
+            *dependency = toml::Value::Table(local);
+        }
+    }
+    Ok(toml::to_string(&manifest)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framwork::natives::code::{MoveOption, UpgradePolicy};
+    use crate::framwork::zip_metadata_str;
+
+    fn module(name: &str) -> ModuleMetadata {
+        ModuleMetadata {
+            name: name.to_string(),
+            source: zip_metadata_str("module 0x1::m {}").unwrap(),
+            source_map: vec![],
+            extension: MoveOption::none(),
+        }
+    }
+
+    fn package(name: &str, modules: Vec<ModuleMetadata>) -> PackageMetadata {
+        PackageMetadata {
+            name: name.to_string(),
+            upgrade_policy: UpgradePolicy::compat(),
+            upgrade_number: 0,
+            source_digest: String::new(),
+            manifest: zip_metadata_str(&format!("[package]\nname = \"{}\"\n", name)).unwrap(),
+            modules,
+            deps: vec![],
+            extension: MoveOption::none(),
+        }
+    }
+
+    fn registries(account: AccountAddress, packages: Vec<PackageMetadata>) -> PackageRegistries {
+        let mut registries = PackageRegistries::new();
+        registries.add_registry(account, PackageRegistry { packages });
+        registries
+    }
+
+    #[test]
+    fn save_sources_writes_valid_module_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = StoredPackage::new(package("pkg", vec![module("coin")]));
+        let written = package.save_sources(dir.path()).unwrap();
+        assert_eq!(written.len(), 2);
+        assert!(dir.path().join("sources").join("coin.move").exists());
+    }
+
+    #[test]
+    fn save_sources_rejects_invalid_module_names() {
+        for name in ["../escape", "/etc/passwd", "a/b", "..", ""] {
+            let dir = tempfile::tempdir().unwrap();
+            let package = StoredPackage::new(package("pkg", vec![module(name)]));
+            assert!(package.save_sources(dir.path()).is_err(), "{}", name);
+            assert!(!dir.path().join("Move.toml").exists(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn reconstruct_writes_valid_package_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let registries = registries(
+            AccountAddress::ONE,
+            vec![package("pkg", vec![module("coin")])],
+        );
+        let written = registries
+            .reconstruct(AccountAddress::ONE, "pkg", dir.path())
+            .unwrap();
+        assert_eq!(written.len(), 1);
+        assert!(dir.path().join("pkg").join("Move.toml").exists());
+    }
+
+    #[test]
+    fn reconstruct_rejects_invalid_package_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = "../escape";
+        let registries = registries(
+            AccountAddress::ONE,
+            vec![package(name, vec![module("coin")])],
+        );
+        assert!(registries
+            .reconstruct(AccountAddress::ONE, name, &dir.path().join("out"))
+            .is_err());
+        assert!(!dir.path().join("escape").exists());
+    }
+
+    #[test]
+    fn reconstruct_accepts_package_names_which_are_no_identifiers() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = "aptos-token";
+        let registries = registries(
+            AccountAddress::ONE,
+            vec![package(name, vec![module("coin")])],
+        );
+        registries
+            .reconstruct(AccountAddress::ONE, name, dir.path())
+            .unwrap();
+        assert!(dir.path().join(name).join("Move.toml").exists());
+    }
+
+    #[test]
+    fn package_names_must_be_directory_names() {
+        for name in ["", ".", "..", "a/b", "a\\b", "../escape"] {
+            assert!(ensure_package_name(name).is_err(), "{}", name);
+        }
+        for name in ["Std", "aptos-token", "my.package"] {
+            assert!(ensure_package_name(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn rewrite_dependencies_rejects_invalid_package_names() {
+        let name = "../../escape";
+        let manifest = format!(
+            "[package]\nname = \"pkg\"\n\n[dependencies]\n\"{}\" = {{ local = \"x\" }}\n",
+            name
+        );
+        let deps = vec![PackageDep {
+            account: AccountAddress::ONE,
+            package_name: name.to_string(),
+        }];
+        assert!(rewrite_dependencies(&manifest, &deps).is_err());
+
+        let manifest = "[package]\nname = \"pkg\"\n\n[dependencies]\nStd = { local = \"x\" }\n";
+        let deps = vec![PackageDep {
+            account: AccountAddress::ONE,
+            package_name: "Std".to_string(),
+        }];
+        let rewritten = rewrite_dependencies(manifest, &deps).unwrap();
+        assert!(rewritten.contains("../Std"));
+    }
+}
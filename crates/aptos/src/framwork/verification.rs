@@ -0,0 +1,155 @@
+use super::module_metadata::APTOS_METADATA_KEY;
+use super::natives::code::PackageMetadata;
+use super::{BuiltPackage, APTOS_METADATA_KEY_V1};
+use anyhow::Context;
+use move_binary_format::CompiledModule;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How a published module compares to the module of the local build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleStatus {
+    /// The bytecode is identical
+    Match,
+    /// The bytecode is identical except for the Aptos runtime metadata
+    MetadataMismatch,
+    Mismatch,
+    /// The module is published, but not part of the local build
+    MissingLocally,
+    /// The module is part of the local build, but was not published
+    MissingOnChain,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleVerification {
+    pub name: String,
+    pub status: ModuleStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageVerification {
+    pub package: String,
+    /// Whether all modules and the source digest match
+    pub verified: bool,
+    pub expected_source_digest: String,
+    pub actual_source_digest: Option<String>,
+    pub source_digest_matches: bool,
+    pub modules: Vec<ModuleVerification>,
+}
+
+/// Compares a local build with the metadata and module bytecode of a published package.
+///
+/// Modules are matched by name. If `ignore_metadata` is set, modules which differ only in
+/// their Aptos runtime metadata count as verified. Note that the source digest covers
+/// `Move.toml`, so it does not match for a package whose manifest was rewritten, e.g. by
+/// `PackageRegistries::reconstruct`.
+pub fn verify_package(
+    package: &BuiltPackage,
+    metadata: &PackageMetadata,
+    published_modules: &[Vec<u8>],
+    ignore_metadata: bool,
+) -> anyhow::Result<PackageVerification> {
+    let mut published = BTreeMap::new();
+    for bytes in published_modules {
+        let module = CompiledModule::deserialize(bytes).context("deserializing a module")?;
+        published.insert(module.self_id().name().to_string(), bytes.as_slice());
+    }
+    let local: BTreeMap<_, _> = package
+        .modules()
+        .map(|module| module.self_id().name().to_string())
+        .zip(package.extract_code())
+        .collect();
+
+    let mut modules = vec![];
+    for (name, local_bytes) in &local {
+        let verification = match published.get(name) {
+            Some(published_bytes) => compare_module(name, local_bytes, published_bytes)?,
+            None => ModuleVerification {
+                name: name.clone(),
+                status: ModuleStatus::MissingOnChain,
+                reason: Some("no bytecode for the module was given".to_string()),
+            },
+        };
+        modules.push(verification);
+    }
+    for name in published.keys().filter(|name| !local.contains_key(*name)) {
+        modules.push(ModuleVerification {
+            name: name.clone(),
+            status: ModuleStatus::MissingLocally,
+            reason: Some("the module is not part of the local package".to_string()),
+        });
+    }
+    for module in &metadata.modules {
+        if !local.contains_key(&module.name) && !published.contains_key(&module.name) {
+            modules.push(ModuleVerification {
+                name: module.name.clone(),
+                status: ModuleStatus::MissingLocally,
+                reason: Some(
+                    "the module is listed in the package metadata, but neither part of the \
+                     local package nor given as bytecode"
+                        .to_string(),
+                ),
+            });
+        }
+    }
+
+    let actual_source_digest = package.source_digest();
+    let source_digest_matches =
+        actual_source_digest.as_deref() == Some(metadata.source_digest.as_str());
+    let verified = source_digest_matches
+        && modules.iter().all(|module| {
+            module.status == ModuleStatus::Match
+                || (ignore_metadata && module.status == ModuleStatus::MetadataMismatch)
+        });
+    Ok(PackageVerification {
+        package: metadata.name.clone(),
+        verified,
+        expected_source_digest: metadata.source_digest.clone(),
+        actual_source_digest,
+        source_digest_matches,
+        modules,
+    })
+}
+
+fn compare_module(
+    name: &str,
+    local_bytes: &[u8],
+    published_bytes: &[u8],
+) -> anyhow::Result<ModuleVerification> {
+    let (status, reason) = if local_bytes == published_bytes {
+        (ModuleStatus::Match, None)
+    } else if strip_aptos_metadata(local_bytes)? == strip_aptos_metadata(published_bytes)? {
+        (
+            ModuleStatus::MetadataMismatch,
+            Some("the Aptos runtime metadata differs".to_string()),
+        )
+    } else {
+        (
+            ModuleStatus::Mismatch,
+            Some(format!(
+                "the bytecode differs ({} bytes locally, {} bytes published)",
+                local_bytes.len(),
+                published_bytes.len()
+            )),
+        )
+    };
+    Ok(ModuleVerification {
+        name: name.to_string(),
+        status,
+        reason,
+    })
+}
+
+/// Serializes a module again without the metadata injected by the Aptos build.
+fn strip_aptos_metadata(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut module = CompiledModule::deserialize(bytes)?;
+    module
+        .metadata
+        .retain(|data| data.key != *APTOS_METADATA_KEY_V1 && data.key != *APTOS_METADATA_KEY);
+    let mut stripped = vec![];
+    module.serialize(&mut stripped)?;
+    Ok(stripped)
+}
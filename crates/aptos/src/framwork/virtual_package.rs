@@ -0,0 +1,92 @@
+use super::{BuildOptions, BuiltPackage, CompiledPackageOutput, DiagnosticsCollector};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A package and its local dependencies given as file contents instead of directories.
+///
+/// All paths are relative to a virtual workspace root. Dependencies are referred to from
+/// `Move.toml` with local paths inside of the workspace, e.g. `{ local = "../framework" }`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VirtualPackage {
+    /// Directory of the root package relative to the workspace root; empty if the root
+    /// package is located at the workspace root.
+    #[serde(default)]
+    pub root: String,
+    /// The contents of every file, keyed by its path relative to the workspace root,
+    /// e.g. `hello/Move.toml` and `hello/sources/hello.move`.
+    pub files: BTreeMap<String, String>,
+}
+
+impl VirtualPackage {
+    /// Compiles the package with the given options and collects its artifacts.
+    ///
+    /// A package which fails to compile is not an error: the output is then not successful
+    /// and carries the diagnostics, with file paths relative to the workspace root.
+    ///
+    /// The Move package system can only read packages from a file system, so the files are
+    /// staged into a fresh directory below `scratch_dir` which is removed again once the
+    /// artifacts have been collected. Nothing outside of that directory is written, including
+    /// the build output, regardless of `options.install_dir`.
+    pub fn compile(
+        &self,
+        options: BuildOptions,
+        scratch_dir: &Path,
+    ) -> anyhow::Result<CompiledPackageOutput> {
+        let workspace = tempfile::Builder::new()
+            .prefix("move-package")
+            .tempdir_in(scratch_dir)
+            .with_context(|| format!("creating a directory in `{}`", scratch_dir.display()))?;
+        self.stage(workspace.path())?;
+
+        let package_path = workspace.path().join(checked_path(&self.root)?);
+        let options = BuildOptions {
+            install_dir: None,
+            ..options
+        };
+        let mut diagnostics = DiagnosticsCollector::new();
+        let result = BuiltPackage::build_with_diagnostics(package_path, options, &mut diagnostics);
+        diagnostics.strip_path_prefix(workspace.path());
+        let output = match result {
+            Ok(package) => CompiledPackageOutput {
+                diagnostics: diagnostics.into_inner(),
+                ..package.to_output()
+            },
+            Err(_) if diagnostics.has_errors() => {
+                CompiledPackageOutput::failed(self.root.clone(), diagnostics.into_inner())
+            }
+            Err(e) => return Err(e),
+        };
+        workspace.close()?;
+        Ok(output)
+    }
+
+    /// Writes all files below `dir`.
+    fn stage(&self, dir: &Path) -> anyhow::Result<()> {
+        for (path, content) in &self.files {
+            let path = dir.join(checked_path(path)?);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, content)
+                .with_context(|| format!("writing `{}`", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes sure a path of the file map stays inside of the workspace.
+fn checked_path(path: &str) -> anyhow::Result<PathBuf> {
+    let path = PathBuf::from(path);
+    for component in path.components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {}
+            _ => bail!(
+                "invalid path `{}`: only relative paths inside of the package are allowed",
+                path.display()
+            ),
+        }
+    }
+    Ok(path)
+}
@@ -0,0 +1,150 @@
+//! Bindings for JavaScript, enabled by the `js` feature.
+//!
+//! The functions take and return plain JS objects and call the [`Toolchain`]. Options are
+//! objects whose keys are the command line options of the corresponding command in camel
+//! case, e.g. `{ namedAddresses: { alice: "0x1234" }, bytecodeVersion: 6 }`.
+//!
+//! Only functions which work on bytes are exported. Compiling a package, or checking a payload
+//! against one, needs the Move package system and with it a file system, which the browser
+//! does not have.
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::parser::parse_type_tag;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+use crate::framwork::{DisassembleOptions, DisassemblyContext};
+use crate::toolchain::{
+    parse_address, parse_function_id, FunctionSource, PayloadArguments, Toolchain,
+};
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ToolchainOptions {
+    named_addresses: BTreeMap<String, String>,
+    bytecode_version: Option<u32>,
+}
+
+impl ToolchainOptions {
+    fn toolchain(&self) -> Result<Toolchain, JsError> {
+        let named_addresses = self
+            .named_addresses
+            .iter()
+            .map(|(name, address)| Ok((name.clone(), parse_address(address)?)))
+            .collect::<Result<BTreeMap<String, AccountAddress>, JsError>>()?;
+        Ok(Toolchain {
+            named_addresses,
+            bytecode_version: self.bytecode_version.unwrap_or(5),
+            skip_fetch_latest_git_deps: true,
+            install_dir: None,
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct JsDisassembleOptions {
+    script: bool,
+    skip_private: bool,
+    skip_code: bool,
+    skip_locals: bool,
+    skip_basic_blocks: bool,
+    /// `text`, the default, `json` for the structured form of a module, or `annotated` for
+    /// the source annotated with the bytecode, which needs `metadata` with the source map
+    output: Option<String>,
+}
+
+/// Disassembles a compiled module, or a script with the `script` option. With the `output`
+/// option set to `json`, a module is returned in a structured form, see `disassemble --output`.
+///
+/// If `metadata`, the BCS of the metadata of the package which contains the module, is given,
+/// the disassembly refers to the source and source map included in it.
+#[wasm_bindgen]
+pub fn disassemble(
+    bytes: &[u8],
+    options: JsValue,
+    metadata: Option<Vec<u8>>,
+) -> Result<JsValue, JsError> {
+    let js_options = options_from_js::<JsDisassembleOptions>(options)?;
+    let options = DisassembleOptions {
+        is_script: js_options.script,
+        skip_private: js_options.skip_private,
+        skip_code: js_options.skip_code,
+        skip_locals: js_options.skip_locals,
+        skip_basic_blocks: js_options.skip_basic_blocks,
+    };
+    let toolchain = Toolchain::default();
+    let context = match &metadata {
+        Some(metadata) => {
+            toolchain
+                .module_with_metadata(bytes.to_vec(), metadata)?
+                .context
+        }
+        None => DisassemblyContext::default(),
+    };
+    match js_options.output.as_deref() {
+        None | Some("text") => to_js(&toolchain.disassemble(bytes, &options, context)?),
+        Some("json") => to_js(&toolchain.disassemble_module(bytes, &options, &context)?),
+        Some("annotated") => to_js(&toolchain.disassemble_annotated(bytes, &options, &context)?),
+        Some(output) => Err(JsError::new(&format!(
+            "unknown output `{}`, expected `text`, `json` or `annotated`",
+            output
+        ))),
+    }
+}
+
+/// Decodes the BCS of the package metadata, see `inspect-metadata`.
+#[wasm_bindgen(js_name = decodeMetadata)]
+pub fn decode_metadata(bytes: &[u8]) -> Result<JsValue, JsError> {
+    to_js(&Toolchain::default().decode_metadata(bytes)?)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildPayloadRequest {
+    function_id: String,
+    #[serde(default)]
+    type_args: Vec<String>,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+    #[serde(flatten)]
+    toolchain: ToolchainOptions,
+}
+
+/// Builds the payload of a transaction which calls an entry function, see `build-payload`.
+///
+/// The arguments of the request are JSON values. They are checked against `module`, the
+/// bytecode of the module which declares the function.
+#[wasm_bindgen(js_name = buildPayload)]
+pub fn build_payload(request: JsValue, module: &[u8]) -> Result<JsValue, JsError> {
+    let request = serde_wasm_bindgen::from_value::<BuildPayloadRequest>(request)?;
+    let (module_id, function) = parse_function_id(&request.function_id)?;
+    let ty_args = request
+        .type_args
+        .iter()
+        .map(|ty| parse_type_tag(ty).map_err(|err| JsError::new(&err.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    to_js(&request.toolchain.toolchain()?.build_payload(
+        &module_id,
+        &function,
+        ty_args,
+        &PayloadArguments::Json(request.args),
+        FunctionSource::Module(module),
+    )?)
+}
+
+/// Deserializes options, which may be left out.
+fn options_from_js<T>(options: JsValue) -> Result<T, JsError>
+where
+    T: Default + for<'de> Deserialize<'de>,
+{
+    if options.is_undefined() || options.is_null() {
+        return Ok(T::default());
+    }
+    Ok(serde_wasm_bindgen::from_value(options)?)
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
@@ -1,12 +1,32 @@
 pub mod framwork;
+#[cfg(feature = "js")]
+pub mod js;
 pub mod move_tool;
+pub mod toolchain;
 pub mod types;
+pub mod vm;
+
+pub use toolchain::Toolchain;
 
 use crate::move_tool::{
-    types::{cli_command::CliCommand, result::CliResult},
+    types::{
+        cli_command::CliCommand,
+        cli_error::CliError,
+        result::{CliResult, ResultWrapper},
+    },
+    BuildPayload,
+    CheckCompatibility,
+    CompileFiles,
     CompilePackage,
     Disassemble,
-    TODO,
+    DocumentPackage,
+    GenBindings,
+    InspectMetadata,
+    PublishPackage,
+    ReconstructPackage,
+    RunFunction,
+    TestPackage,
+    VerifyPackage,
     Interactive
 };
 use clap::Parser;
@@ -16,10 +36,19 @@ use clap::Parser;
 #[clap(name = "aptos", author, version, propagate_version = true)]
 pub enum Tool {
     Interactive(Interactive),
+    BuildPayload(BuildPayload),
+    CheckCompatibility(CheckCompatibility),
     Compile(CompilePackage),
+    CompileFiles(CompileFiles),
     Disassemble(Disassemble),
-    Run(TODO),
-    Test(TODO)
+    Document(DocumentPackage),
+    GenBindings(GenBindings),
+    InspectMetadata(InspectMetadata),
+    Publish(PublishPackage),
+    Reconstruct(ReconstructPackage),
+    Run(RunFunction),
+    Test(TestPackage),
+    VerifyPackage(VerifyPackage),
 }
 
 impl Tool {
@@ -27,10 +56,45 @@ impl Tool {
         use Tool::*;
         match self {
             Interactive(tool) => tool.execute_serialized(),
+            BuildPayload(tool) => tool.execute_serialized(),
+            CheckCompatibility(tool) => tool.execute_serialized(),
             Compile(tool) => tool.execute_serialized(),
+            CompileFiles(tool) => tool.execute_serialized(),
             Disassemble(tool) => tool.execute_serialized(),
+            Document(tool) => tool.execute_serialized(),
+            GenBindings(tool) => tool.execute_serialized(),
+            InspectMetadata(tool) => tool.execute_serialized(),
+            Publish(tool) => tool.execute_serialized(),
+            Reconstruct(tool) => tool.execute_serialized(),
             Run(tool)=> tool.execute_serialized(),
-            Test(tool)=> tool.execute_serialized()
+            Test(tool)=> tool.execute_serialized(),
+            VerifyPackage(tool) => tool.execute_serialized(),
+        }
+    }
+
+    /// Executes the command and returns its output as a JSON value instead of a string
+    pub fn execute_value(self) -> ResultWrapper<serde_json::Value> {
+        use Tool::*;
+        match self {
+            Interactive(_) => ResultWrapper::Error(
+                CliError::CommandArgumentError(
+                    "interactive sessions cannot be nested".to_string(),
+                )
+                .to_string(),
+            ),
+            BuildPayload(tool) => tool.execute_value(),
+            CheckCompatibility(tool) => tool.execute_value(),
+            Compile(tool) => tool.execute_value(),
+            CompileFiles(tool) => tool.execute_value(),
+            Disassemble(tool) => tool.execute_value(),
+            Document(tool) => tool.execute_value(),
+            GenBindings(tool) => tool.execute_value(),
+            InspectMetadata(tool) => tool.execute_value(),
+            Publish(tool) => tool.execute_value(),
+            Reconstruct(tool) => tool.execute_value(),
+            Run(tool) => tool.execute_value(),
+            Test(tool) => tool.execute_value(),
+            VerifyPackage(tool) => tool.execute_value(),
         }
     }
 }
@@ -0,0 +1,117 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::toolchain::{BuildPayloadOutput, FunctionSource, PayloadArguments};
+
+use super::types::{
+    cli_command::CliCommand, cli_error::CliError, member_id::MemberId,
+    move_package_dir::MovePackageDir, result::CliTypedResult, utils::set_bytecode_version,
+};
+use move_core_types::parser::parse_type_tag;
+
+/// Build the payload of a transaction which calls an entry function
+///
+/// The arguments are checked against the ABI of the function, or against the signature of the
+/// function in the compiled module if there is no ABI, and BCS encoded. The output contains
+/// the BCS encoded `TransactionPayload` and its JSON representation, ready to be signed.
+#[derive(Parser)]
+pub struct BuildPayload {
+    /// Function name as `<ADDRESS>::<MODULE_ID>::<FUNCTION_NAME>`
+    ///
+    /// Example: `0x1::coin::transfer`
+    #[clap(long)]
+    pub(crate) function_id: MemberId,
+
+    /// TypeTag arguments separated by spaces.
+    ///
+    /// Example: `u8 u64 u128 bool address vector<u8> 0x1::aptos_coin::AptosCoin`
+    #[clap(long, multiple_values = true)]
+    pub(crate) type_args: Vec<String>,
+
+    /// Arguments separated by spaces, without the signers. They are parsed according to the
+    /// types of the parameters, vectors are given as JSON arrays.
+    ///
+    /// Example: `5 true 0x1 alice "hello" 0x0a0b '[1, 2, 3]'`
+    #[clap(long, multiple_values = true)]
+    pub(crate) args: Vec<String>,
+
+    /// Arguments as a JSON array, without the signers
+    ///
+    /// Example: `'[5, true, "0x1", "hello", ["0x1", "0x2"]]'`
+    #[clap(long, conflicts_with = "args")]
+    pub(crate) json_args: Option<String>,
+
+    /// Path to the compiled module which declares the function
+    ///
+    /// If set, the arguments are checked against the module and no package is built.
+    #[clap(long, parse(from_os_str))]
+    pub(crate) bytecode: Option<PathBuf>,
+
+    /// The hex encoded bytecode of the module which declares the function, instead of reading
+    /// it from a file
+    #[clap(long, conflicts_with = "bytecode")]
+    pub(crate) module_bytes: Option<String>,
+
+    /// Path to write the BCS encoded payload to
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<BuildPayloadOutput> for BuildPayload {
+    fn command_name(&self) -> &'static str {
+        "BuildPayload"
+    }
+
+    fn execute(self) -> CliTypedResult<BuildPayloadOutput> {
+        let ty_args = self
+            .type_args
+            .iter()
+            .map(|ty| {
+                parse_type_tag(ty)
+                    .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        let args = match &self.json_args {
+            Some(json_args) => PayloadArguments::Json(
+                serde_json::from_str(json_args)
+                    .map_err(|err| CliError::UnableToParse("--json-args", err.to_string()))?,
+            ),
+            None => PayloadArguments::Strings(self.args.clone()),
+        };
+        let module_bytes = match (&self.module_bytes, &self.bytecode) {
+            (Some(hex_bytes), _) => Some(
+                hex::decode(hex_bytes.strip_prefix("0x").unwrap_or(hex_bytes))
+                    .map_err(|err| CliError::UnableToParse("--module-bytes", err.to_string()))?,
+            ),
+            (None, Some(path)) => Some(
+                std::fs::read(path).map_err(|err| CliError::IO(path.display().to_string(), err))?,
+            ),
+            (None, None) => None,
+        };
+        let package_path;
+        let source = match &module_bytes {
+            Some(bytes) => FunctionSource::Module(bytes),
+            None => {
+                set_bytecode_version(self.move_options.bytecode_version);
+                package_path = self.move_options.get_package_path()?;
+                FunctionSource::Package(&package_path)
+            }
+        };
+
+        let output = self.move_options.toolchain().build_payload(
+            &self.function_id.module_id,
+            &self.function_id.member_id,
+            ty_args,
+            &args,
+            source,
+        )?;
+        if let Some(path) = &self.output_file {
+            std::fs::write(path, &output.bytes)
+                .map_err(|err| CliError::IO(path.display().to_string(), err))?;
+        }
+        Ok(output)
+    }
+}
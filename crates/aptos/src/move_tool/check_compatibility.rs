@@ -0,0 +1,62 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::framwork::CompatibilityReport;
+
+use super::types::{
+    cli_command::CliCommand, cli_error::CliError, move_package_dir::MovePackageDir,
+    result::CliTypedResult, utils::set_bytecode_version,
+};
+
+/// Check whether a local package may upgrade a published version of it
+///
+/// The local package is compiled and compared with the published modules according to the
+/// upgrade policy of the published package metadata. Every violation is listed with the
+/// module and the struct or function which breaks the policy.
+#[derive(Parser)]
+pub struct CheckCompatibility {
+    /// Path to the BCS encoded `PackageMetadata` of the published package
+    #[clap(long, parse(from_os_str))]
+    pub(crate) old_metadata: PathBuf,
+
+    /// Paths to the published bytecode of the modules, separated by spaces
+    #[clap(long, parse(from_os_str), multiple_values = true)]
+    pub(crate) old_modules: Vec<PathBuf>,
+
+    /// Also check that friend functions keep their signature and friends are not removed
+    #[clap(long)]
+    pub(crate) check_friends: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<CompatibilityReport> for CheckCompatibility {
+    fn command_name(&self) -> &'static str {
+        "CheckCompatibility"
+    }
+
+    fn is_failure(output: &CompatibilityReport) -> bool {
+        !output.compatible
+    }
+
+    fn execute(self) -> CliTypedResult<CompatibilityReport> {
+        let read = |path: &PathBuf| {
+            std::fs::read(path).map_err(|err| CliError::IO(path.display().to_string(), err))
+        };
+        let old_metadata = read(&self.old_metadata)?;
+        let old_modules = self
+            .old_modules
+            .iter()
+            .map(read)
+            .collect::<CliTypedResult<Vec<_>>>()?;
+
+        set_bytecode_version(self.move_options.bytecode_version);
+        Ok(self.move_options.toolchain().check_compatibility(
+            &self.move_options.get_package_path()?,
+            &old_metadata,
+            &old_modules,
+            self.check_friends,
+        )?)
+    }
+}
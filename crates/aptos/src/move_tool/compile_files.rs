@@ -0,0 +1,98 @@
+use clap::Parser;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::framwork::{CompiledPackageOutput, VirtualPackage};
+use crate::toolchain::Toolchain;
+
+use super::types::{
+    account_address_wrapper::AccountAddressWrapper,
+    cli_command::CliCommand,
+    cli_error::CliError,
+    result::CliTypedResult,
+    utils::{parse_map, set_bytecode_version},
+};
+use super::IncludedArtifactsArgs;
+
+/// Compiles a package given as a map of file paths to file contents
+///
+/// The package is described by JSON of the form `{"root": "hello", "files":
+/// {"hello/Move.toml": "...", "hello/sources/hello.move": "..."}}`. Dependencies have to be
+/// part of the file map and be referred to with local paths from `Move.toml`.
+#[derive(Parser)]
+pub struct CompileFiles {
+    /// Path to a JSON file describing the package
+    #[clap(long, parse(from_os_str), required_unless_present = "json")]
+    pub(crate) input: Option<PathBuf>,
+
+    /// The JSON describing the package, instead of reading it from a file
+    #[clap(long, conflicts_with = "input")]
+    pub(crate) json: Option<String>,
+
+    /// Named addresses for the move binary
+    ///
+    /// Example: alice=0x1234, bob=0x5678
+    #[clap(long, parse(try_from_str = parse_map), default_value = "")]
+    pub(crate) named_addresses: BTreeMap<String, AccountAddressWrapper>,
+
+    /// Specify the version of the bytecode the compiler is going to emit.
+    #[clap(long)]
+    pub(crate) bytecode_version: Option<u32>,
+
+    /// Directory in which the files are temporarily staged for compilation
+    ///
+    /// The Move package system only reads packages from a file system. On wasm32-wasi this
+    /// has to be a preopened directory.
+    #[clap(long, parse(from_os_str))]
+    pub(crate) scratch_dir: PathBuf,
+
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+}
+
+impl CompileFiles {
+    fn load_package(&self) -> CliTypedResult<VirtualPackage> {
+        let json = match (&self.json, &self.input) {
+            (Some(json), _) => json.clone(),
+            (None, Some(input)) => std::fs::read_to_string(input)
+                .map_err(|err| CliError::IO(input.display().to_string(), err))?,
+            (None, None) => {
+                return Err(CliError::CommandArgumentError(
+                    "Either '--input' or '--json' must be provided".to_string(),
+                ))
+            }
+        };
+        serde_json::from_str(&json)
+            .map_err(|err| CliError::UnableToParse("package", err.to_string()))
+    }
+}
+
+impl CliCommand<CompiledPackageOutput> for CompileFiles {
+    fn command_name(&self) -> &'static str {
+        "CompileFiles"
+    }
+
+    fn is_failure(output: &CompiledPackageOutput) -> bool {
+        !output.success
+    }
+
+    fn execute(self) -> CliTypedResult<CompiledPackageOutput> {
+        let package = self.load_package()?;
+        set_bytecode_version(self.bytecode_version);
+        let named_addresses = self
+            .named_addresses
+            .iter()
+            .map(|(name, address)| (name.clone(), address.account_address))
+            .collect();
+        let toolchain = Toolchain {
+            named_addresses,
+            bytecode_version: self.bytecode_version.unwrap_or(5),
+            skip_fetch_latest_git_deps: true,
+            install_dir: None,
+        };
+        Ok(toolchain.compile_files(
+            &package,
+            self.included_artifacts_args.included_artifacts,
+            &self.scratch_dir,
+        )?)
+    }
+}
@@ -0,0 +1,38 @@
+use clap::Parser;
+
+use crate::framwork::docgen::DocgenOptions;
+
+use super::types::{
+    cli_command::CliCommand, move_package_dir::MovePackageDir, result::CliTypedResult,
+};
+
+/// Generate documentation for a package and its dependencies
+///
+/// One markdown file per module is written to the `doc` directory of the package. The
+/// paths of the written files are returned.
+#[derive(Parser)]
+pub struct DocumentPackage {
+    #[clap(flatten)]
+    pub(crate) docgen_options: DocgenOptions,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<Vec<String>> for DocumentPackage {
+    fn command_name(&self) -> &'static str {
+        "DocumentPackage"
+    }
+
+    fn execute(self) -> CliTypedResult<Vec<String>> {
+        let package_path = self.move_options.get_package_path()?;
+        let files = self
+            .move_options
+            .toolchain()
+            .document(&package_path, &self.docgen_options)?;
+        Ok(files
+            .into_iter()
+            .map(|path| path.display().to_string())
+            .collect())
+    }
+}
@@ -0,0 +1,63 @@
+use clap::{ArgEnum, Parser};
+use std::path::PathBuf;
+
+use super::types::{
+    cli_command::CliCommand, cli_error::CliError, move_package_dir::MovePackageDir,
+    result::CliTypedResult, utils::set_bytecode_version,
+};
+
+/// Generate client bindings for the entry and view functions of a package
+///
+/// One file per module is written, with functions which build the payloads of the entry
+/// functions, helpers for the type tags of the structs and an interface for the view
+/// functions. The paths of the written files are returned.
+#[derive(Parser)]
+pub struct GenBindings {
+    /// The language of the bindings
+    #[clap(long, arg_enum, default_value = "ts")]
+    pub(crate) lang: BindingsLanguage,
+
+    /// Directory to write the bindings to
+    ///
+    /// Defaults to `<package_dir>/bindings/<lang>`
+    #[clap(long, parse(from_os_str))]
+    pub(crate) bindings_dir: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum BindingsLanguage {
+    Ts,
+}
+
+impl CliCommand<Vec<String>> for GenBindings {
+    fn command_name(&self) -> &'static str {
+        "GenBindings"
+    }
+
+    fn execute(self) -> CliTypedResult<Vec<String>> {
+        set_bytecode_version(self.move_options.bytecode_version);
+        let package_path = self.move_options.get_package_path()?;
+        let toolchain = self.move_options.toolchain();
+        let (files, lang_dir) = match self.lang {
+            BindingsLanguage::Ts => (toolchain.generate_ts_bindings(&package_path)?, "ts"),
+        };
+
+        let dir = self
+            .bindings_dir
+            .unwrap_or_else(|| package_path.join("bindings").join(lang_dir));
+        std::fs::create_dir_all(&dir)
+            .map_err(|err| CliError::IO(dir.display().to_string(), err))?;
+        files
+            .into_iter()
+            .map(|(name, content)| {
+                let path = dir.join(name);
+                std::fs::write(&path, content)
+                    .map_err(|err| CliError::IO(path.display().to_string(), err))?;
+                Ok(path.display().to_string())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,75 @@
+use clap::Parser;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::framwork::PackageMetadataView;
+use crate::toolchain::Toolchain;
+
+use super::types::{cli_command::CliCommand, cli_error::CliError, result::CliTypedResult};
+
+/// Decode the package metadata written by `compile --save-metadata`
+///
+/// Prints the metadata with the manifest, sources and source maps decompressed. The
+/// manifest and sources can also be written back into a directory.
+#[derive(Parser)]
+pub struct InspectMetadata {
+    /// Path to a `package-metadata.bcs` file
+    #[clap(long, parse(from_os_str), required_unless_present = "bytes")]
+    pub(crate) metadata_file: Option<PathBuf>,
+
+    /// The hex encoded BCS of the metadata, instead of reading it from a file
+    #[clap(long, conflicts_with = "metadata_file")]
+    pub(crate) bytes: Option<String>,
+
+    /// Directory to write `Move.toml` and the module sources into
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InspectMetadataOutput {
+    #[serde(flatten)]
+    pub metadata: PackageMetadataView,
+    /// The files written to the output directory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extracted_files: Vec<String>,
+}
+
+impl InspectMetadata {
+    fn load_bytes(&self) -> CliTypedResult<Vec<u8>> {
+        match (&self.bytes, &self.metadata_file) {
+            (Some(bytes), _) => hex::decode(bytes.strip_prefix("0x").unwrap_or(bytes))
+                .map_err(|err| CliError::UnableToParse("bytes", err.to_string())),
+            (None, Some(path)) => {
+                std::fs::read(path).map_err(|err| CliError::IO(path.display().to_string(), err))
+            }
+            (None, None) => Err(CliError::CommandArgumentError(
+                "Either '--metadata-file' or '--bytes' must be provided".to_string(),
+            )),
+        }
+    }
+}
+
+impl CliCommand<InspectMetadataOutput> for InspectMetadata {
+    fn command_name(&self) -> &'static str {
+        "InspectMetadata"
+    }
+
+    fn execute(self) -> CliTypedResult<InspectMetadataOutput> {
+        let bytes = self.load_bytes()?;
+        let toolchain = Toolchain::default();
+        let metadata = toolchain.decode_metadata(&bytes)?;
+        let extracted_files = match &self.output_dir {
+            Some(dir) => toolchain
+                .extract_metadata_sources(&bytes, dir)?
+                .into_iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            None => vec![],
+        };
+        Ok(InspectMetadataOutput {
+            metadata,
+            extracted_files,
+        })
+    }
+}
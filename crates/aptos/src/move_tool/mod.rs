@@ -1,36 +1,55 @@
 pub mod types;
 
+mod build_payload;
+mod check_compatibility;
+mod compile_files;
+mod document;
+mod gen_bindings;
+mod inspect_metadata;
+mod publish;
+mod reconstruct;
+mod run;
+mod test;
+mod verify_package;
+
+pub use build_payload::BuildPayload;
+pub use check_compatibility::CheckCompatibility;
+pub use compile_files::CompileFiles;
+pub use document::DocumentPackage;
+pub use gen_bindings::{BindingsLanguage, GenBindings};
+pub use inspect_metadata::InspectMetadata;
+pub use publish::PublishPackage;
+pub use reconstruct::ReconstructPackage;
+pub use run::RunFunction;
+pub use test::TestPackage;
+pub use verify_package::VerifyPackage;
+
 use clap::{ArgEnum, Parser};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
-    fmt::{Display, Formatter},
+    ffi::OsStr,
     fs,
-    path::Path,
-    str::FromStr,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
 };
 
-use crate::framwork::{BuildOptions, BuiltPackage};
+use crate::framwork::{
+    Diagnostic, DisassembleOptions, DisassemblyContext, IncludedArtifacts, ModuleDisassembly,
+};
+use crate::toolchain::{DisassemblyInput, Toolchain};
 
 use super::move_tool::types::{
-    cli_command::CliCommand, cli_error::CliError, result::CliTypedResult,
+    cli_command::CliCommand,
+    cli_error::CliError,
+    result::{CliTypedResult, ResultWrapper},
     utils::set_bytecode_version,
 };
 pub use move_core_types::account_address::AccountAddress;
 use types::move_package_dir::MovePackageDir;
 
-use move_binary_format::{
-    binary_views::BinaryIndexedView,
-    file_format::{CompiledModule, CompiledScript},
-};
-
 use move_coverage::coverage_map::CoverageMap;
 
-use move_bytecode_source_map::{mapping::SourceMapping, utils::source_map_from_file};
-use move_command_line_common::files::{
-    MOVE_COMPILED_EXTENSION, MOVE_EXTENSION, SOURCE_MAP_EXTENSION,
-};
-use move_disassembler::disassembler::{Disassembler, DisassemblerOptions};
-use move_ir_types::location::Spanned;
+use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
 
 #[derive(Parser)]
 pub struct IncludedArtifactsArgs {
@@ -47,82 +66,6 @@ pub struct IncludedArtifactsArgs {
     pub(crate) included_artifacts: IncludedArtifacts,
 }
 
-#[derive(ArgEnum, Clone, Copy, Debug)]
-pub enum IncludedArtifacts {
-    None,
-    Sparse,
-    All,
-}
-
-impl Display for IncludedArtifacts {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        use IncludedArtifacts::*;
-        match self {
-            None => f.write_str("none"),
-            Sparse => f.write_str("sparse"),
-            All => f.write_str("all"),
-        }
-    }
-}
-
-impl FromStr for IncludedArtifacts {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use IncludedArtifacts::*;
-        match s {
-            "none" => Ok(None),
-            "sparse" => Ok(Sparse),
-            "all" => Ok(All),
-            _ => Err("unknown variant"),
-        }
-    }
-}
-
-impl IncludedArtifacts {
-    pub(crate) fn build_options(
-        self,
-        skip_fetch_latest_git_deps: bool,
-        named_addresses: BTreeMap<String, AccountAddress>,
-        bytecode_version: u32,
-    ) -> BuildOptions {
-        use IncludedArtifacts::*;
-        match self {
-            None => BuildOptions {
-                with_srcs: false,
-                with_abis: false,
-                with_source_maps: false,
-                // Always enable error map bytecode injection
-                with_error_map: true,
-                named_addresses,
-                skip_fetch_latest_git_deps,
-                bytecode_version: Some(bytecode_version),
-                ..BuildOptions::default()
-            },
-            Sparse => BuildOptions {
-                with_srcs: true,
-                with_abis: false,
-                with_source_maps: false,
-                with_error_map: true,
-                named_addresses,
-                skip_fetch_latest_git_deps,
-                bytecode_version: Some(bytecode_version),
-                ..BuildOptions::default()
-            },
-            All => BuildOptions {
-                with_srcs: true,
-                with_abis: true,
-                with_source_maps: true,
-                with_error_map: true,
-                named_addresses,
-                skip_fetch_latest_git_deps,
-                bytecode_version: Some(bytecode_version),
-                ..BuildOptions::default()
-            },
-        }
-    }
-}
-
 /// Compiles a package and returns the associated ModuleIds
 #[derive(Parser)]
 pub struct CompilePackage {
@@ -138,8 +81,22 @@ pub struct CompilePackage {
     pub(crate) move_options: MovePackageDir,
 }
 
-/// TODO
-/// Disassemble the Move bytecode pointed to
+/// The result of compiling a package
+#[derive(Debug, Serialize)]
+pub struct CompilePackageOutput {
+    /// Whether the package compiled; if not, `diagnostics` contains at least one error and
+    /// the command exits with an error code
+    pub success: bool,
+    /// The ids of the modules of the package
+    pub modules: Vec<String>,
+    /// The errors and warnings of the compiler and of the extended checks
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Disassemble Move bytecode
+///
+/// The bytecode is read from a `.mv` file, given as hex or base64, or taken from a module or
+/// script of the package in `--package-dir`.
 #[derive(Parser)]
 pub struct Disassemble {
     /// Skip printing of private functions.
@@ -164,197 +121,308 @@ pub struct Disassemble {
 
     /// The path to the bytecode file to disassemble; let's call it file.mv. We assume that two
     /// other files reside under the same directory: a source map file.mvsm (possibly) and the Move
-    /// source code file.move.
-    #[clap(short = 'b', long = "bytecode")]
-    pub bytecode_file_path: String,
+    /// source code file.move. For a file in the build directory of a package, they are looked
+    /// up in the `source_maps` and `sources` directories of the build instead.
+    #[clap(
+        short = 'b',
+        long = "bytecode",
+        conflicts_with_all = &["bytecode_hex", "bytecode_base64", "module"]
+    )]
+    pub bytecode_file_path: Option<String>,
+
+    /// The hex encoded bytecode to disassemble, instead of reading it from a file
+    #[clap(long, conflicts_with_all = &["bytecode_base64", "module"])]
+    pub bytecode_hex: Option<String>,
+
+    /// The base64 encoded bytecode to disassemble, instead of reading it from a file
+    #[clap(long, conflicts_with = "module")]
+    pub bytecode_base64: Option<String>,
+
+    /// Name of a module or script of the package in `--package-dir` to disassemble
+    ///
+    /// The package is built, and the source and source map are taken from the build.
+    #[clap(long)]
+    pub module: Option<String>,
+
+    /// Path to the `package-metadata.bcs` of the package which contains the module
+    ///
+    /// The source and source map of the module are taken from the metadata, as far as they were
+    /// included into it.
+    #[clap(long, parse(from_os_str), conflicts_with = "module")]
+    pub metadata: Option<PathBuf>,
 
     /// (Optional) Path to a coverage file for the VM in order to print trace information in the
     /// disassembled output.
     #[clap(short = 'c', long = "move-coverage-path")]
     pub code_coverage_path: Option<String>,
+
+    /// The format of the disassembly
+    ///
+    /// `json` returns the handles, structs, functions, constants, friends and the Aptos
+    /// metadata of a module in a structured form. The coverage is not part of it.
+    ///
+    /// `annotated` prints each line of the source followed by the instructions generated from
+    /// it, with their execution counts if a coverage file is given. It needs the source and
+    /// the source map, see `--bytecode`, `--module` and `--metadata`.
+    #[clap(long, arg_enum, default_value = "text")]
+    pub output: DisassemblyFormat,
+
+    #[clap(flatten)]
+    pub move_options: MovePackageDir,
 }
 
-/// Start a explorer
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum DisassemblyFormat {
+    Text,
+    Json,
+    Annotated,
+}
+
+/// The disassembly in the requested format.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum DisassembleOutput {
+    Text(String),
+    Json(ModuleDisassembly),
+}
+
+/// Start an interactive session driven by JSON requests on stdin
+///
+/// Each line on stdin is a request of the form `{"id": 1, "command": "compile", "args":
+/// ["--package-dir", "hello"]}`, where `command` is any subcommand of this tool and `args`
+/// its command line arguments. For each request a single line is written to stdout, of the
+/// form `{"id": 1, "Result": ...}` or `{"id": 1, "Error": "..."}`, carrying the id of the
+/// request. Failing requests do not end the session; it ends with the `exit` command or when
+/// stdin is closed.
 #[derive(Parser)]
 #[clap(name = "interactive")]
-pub struct Interactive {
-    #[clap(long = "order-path")]
-    pub order_path: Option<String>,
-    #[clap(long = "output-path")]
-    pub output_path: Option<String>,
+pub struct Interactive {}
+
+/// A request read from stdin in interactive mode.
+#[derive(Deserialize)]
+struct InteractiveRequest {
+    #[serde(default)]
+    id: serde_json::Value,
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// A response written to stdout in interactive mode.
+#[derive(Serialize)]
+struct InteractiveResponse {
+    id: serde_json::Value,
+    #[serde(flatten)]
+    payload: ResultWrapper<serde_json::Value>,
+}
+
+const EXIT_COMMAND: &str = "exit";
+
+impl Interactive {
+    fn handle_request(request: InteractiveRequest) -> InteractiveResponse {
+        let argv = std::iter::once(String::new())
+            .chain(std::iter::once(request.command))
+            .chain(request.args.into_iter());
+        let payload = match crate::Tool::try_parse_from(argv) {
+            Ok(tool) => tool.execute_value(),
+            Err(err) => ResultWrapper::Error(err.to_string()),
+        };
+        InteractiveResponse {
+            id: request.id,
+            payload,
+        }
+    }
 }
 
 impl CliCommand<Vec<String>> for Interactive {
     fn command_name(&self) -> &'static str {
-        "InteractiveA"
+        "Interactive"
     }
 
     fn execute(self) -> CliTypedResult<Vec<String>> {
-        loop {
-            const DEFAULT_CONNAND: &str = "/workspace/order/";
-            const DEFAULT_OK: &str = "/workspace/order/result_ok";
-            const DEFAULT_ERR: &str = "/workspace/order/result_err";
-
-            let s = match self.order_path {
-                Some(ref v) => v.clone(),
-                None => String::from(DEFAULT_CONNAND),
-            };
-
-            let path = std::path::Path::new(&s);
-            while !path.exists() {
-                let t = std::time::Duration::from_millis(1000);
-                std::thread::sleep(t);
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+        for line in stdin.lock().lines() {
+            let line = line.map_err(|err| CliError::IO("stdin".to_string(), err))?;
+            if line.trim().is_empty() {
+                continue;
             }
 
-            let s = String::from_utf8(fs::read(&s).unwrap()).unwrap();
-
-            if s == "exit" {
-                break;
-            }
-
-            let mut test: Vec<&str> = s.split(",").collect();
-            test.insert(0, "");
-
-            match crate::Tool::parse_from(test).execute() {
-                Ok(inner) => println!("{}", inner),
-                Err(inner) => {
-                    println!("{}", inner);
-                }
-            }
+            let response = match serde_json::from_str::<InteractiveRequest>(&line) {
+                Ok(request) if request.command == EXIT_COMMAND => break,
+                Ok(request) => Self::handle_request(request),
+                Err(err) => InteractiveResponse {
+                    id: serde_json::Value::Null,
+                    payload: ResultWrapper::Error(
+                        CliError::UnableToParse("request", err.to_string()).to_string(),
+                    ),
+                },
+            };
 
-            fs::remove_file(path);
+            let response = serde_json::to_string(&response)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            let mut stdout = stdout.lock();
+            writeln!(stdout, "{}", response)
+                .and_then(|_| stdout.flush())
+                .map_err(|err| CliError::IO("stdout".to_string(), err))?;
         }
 
         Ok(vec!["Exit".to_string()])
     }
 }
 
-/// TODO
-#[derive(Parser)]
-#[clap(name = "TODO")]
-pub struct TODO {}
-
-impl CliCommand<Vec<String>> for TODO {
-    fn command_name(&self) -> &'static str {
-        "TODO"
-    }
-    fn execute(self) -> CliTypedResult<Vec<String>> {
-        unimplemented!("TODO")
-    }
-}
-
-impl CliCommand<String> for Disassemble {
+impl CliCommand<DisassembleOutput> for Disassemble {
     fn command_name(&self) -> &'static str {
         "Disassemble"
     }
 
-    fn execute(self) -> CliTypedResult<String> {
-        let move_extension = MOVE_EXTENSION;
-        let mv_bytecode_extension = MOVE_COMPILED_EXTENSION;
-        let source_map_extension = SOURCE_MAP_EXTENSION;
-
-        let source_path = Path::new(&self.bytecode_file_path);
-        let extension = source_path
-            .extension()
-            .expect("Missing file extension for bytecode file");
-        if extension != mv_bytecode_extension {
-            println!(
-                "Bad source file extension {:?}; expected {}",
-                extension, mv_bytecode_extension
-            );
-            std::process::exit(1);
-        }
-
-        let bytecode_bytes =
-            fs::read(&self.bytecode_file_path).expect("Unable to read bytecode file");
-
-        let source_path = Path::new(&self.bytecode_file_path).with_extension(move_extension);
-        let source = fs::read_to_string(&source_path).ok();
-        let source_map = source_map_from_file(
-            &Path::new(&self.bytecode_file_path).with_extension(source_map_extension),
-        );
-
-        let mut disassembler_options = DisassemblerOptions::new();
-        disassembler_options.print_code = !self.skip_code;
-        disassembler_options.only_externally_visible = self.skip_private;
-        disassembler_options.print_basic_blocks = !self.skip_basic_blocks;
-        disassembler_options.print_locals = !self.skip_locals;
-
-        // TODO: make source mapping work with the Move source language
-        let no_loc = Spanned::unsafe_no_loc(()).loc;
-        let module: CompiledModule;
-        let script: CompiledScript;
-        let bytecode = if self.is_script {
-            script = CompiledScript::deserialize(&bytecode_bytes)
-                .expect("Script blob can't be deserialized");
-            BinaryIndexedView::Script(&script)
-        } else {
-            module = CompiledModule::deserialize(&bytecode_bytes)
-                .expect("Module blob can't be deserialized");
-            BinaryIndexedView::Module(&module)
-        };
-
-        let mut source_mapping = {
-            if let Ok(s) = source_map {
-                SourceMapping::new(s, bytecode)
-            } else {
-                SourceMapping::new_from_view(bytecode, no_loc)
-                    .expect("Unable to build dummy source mapping")
+    fn execute(self) -> CliTypedResult<DisassembleOutput> {
+        let toolchain = self.move_options.toolchain();
+        let input = match &self.module {
+            Some(name) => {
+                set_bytecode_version(self.move_options.bytecode_version);
+                let package_path = self.move_options.get_package_path()?;
+                toolchain.package_unit(&package_path, name)?
             }
+            None => self.bytecode_input(&toolchain)?,
         };
 
-        if let Some(source_code) = source {
-            source_mapping
-                .with_source_code((source_path.to_str().unwrap().to_string(), source_code));
-        }
-
-        let mut disassembler = Disassembler::new(source_mapping, disassembler_options);
-
+        let mut options = self.options();
+        options.is_script |= input.is_script;
+        let mut context = input.context;
         if let Some(file_path) = &self.code_coverage_path {
-            disassembler.add_coverage_map(
+            context.coverage = Some(
                 CoverageMap::from_binary_file(file_path)
-                    .unwrap()
+                    .map_err(|err| CliError::UnableToReadFile(file_path.clone(), err.to_string()))?
                     .to_unified_exec_map(),
             );
         }
+        Ok(match self.output {
+            DisassemblyFormat::Text => DisassembleOutput::Text(
+                toolchain
+                    .disassemble(&input.bytecode, &options, context)?
+                    .disassembly,
+            ),
+            DisassemblyFormat::Json => DisassembleOutput::Json(toolchain.disassemble_module(
+                &input.bytecode,
+                &options,
+                &context,
+            )?),
+            DisassemblyFormat::Annotated => DisassembleOutput::Text(
+                toolchain.disassemble_annotated(&input.bytecode, &options, &context)?,
+            ),
+        })
+    }
+}
 
-        let dissassemble_string = disassembler.disassemble().expect("Unable to dissassemble");
+impl Disassemble {
+    /// Reads the bytecode given by `--bytecode`, `--bytecode-hex` or `--bytecode-base64`. The
+    /// source and source map are taken from the metadata if given, or else from the files next
+    /// to the bytecode file.
+    fn bytecode_input(&self, toolchain: &Toolchain) -> CliTypedResult<DisassemblyInput> {
+        let (bytecode, path) = match (
+            &self.bytecode_file_path,
+            &self.bytecode_hex,
+            &self.bytecode_base64,
+        ) {
+            (Some(file_path), _, _) => {
+                let path = Path::new(file_path);
+                if path.extension() != Some(OsStr::new(MOVE_COMPILED_EXTENSION)) {
+                    return Err(CliError::CommandArgumentError(format!(
+                        "Bad bytecode file extension of {}; expected {}",
+                        file_path, MOVE_COMPILED_EXTENSION
+                    )));
+                }
+                let bytecode =
+                    fs::read(path).map_err(|err| CliError::IO(file_path.clone(), err))?;
+                (bytecode, Some(path))
+            }
+            (None, Some(hex_bytes), _) => (
+                hex::decode(hex_bytes.strip_prefix("0x").unwrap_or(hex_bytes))
+                    .map_err(|err| CliError::UnableToParse("--bytecode-hex", err.to_string()))?,
+                None,
+            ),
+            (None, None, Some(base64_bytes)) => (
+                base64::decode(base64_bytes)
+                    .map_err(|err| CliError::UnableToParse("--bytecode-base64", err.to_string()))?,
+                None,
+            ),
+            (None, None, None) => {
+                return Err(CliError::CommandArgumentError(
+                    "One of --bytecode, --bytecode-hex, --bytecode-base64 or --module is required"
+                        .to_string(),
+                ))
+            }
+        };
 
-        println!("{}", dissassemble_string);
+        if let Some(metadata_path) = &self.metadata {
+            let metadata = fs::read(metadata_path)
+                .map_err(|err| CliError::IO(metadata_path.display().to_string(), err))?;
+            return Ok(toolchain.module_with_metadata(bytecode, &metadata)?);
+        }
+        let context = match path {
+            Some(path) => DisassemblyContext::for_bytecode_file(path),
+            None => DisassemblyContext::default(),
+        };
+        Ok(DisassemblyInput {
+            bytecode,
+            is_script: false,
+            context,
+        })
+    }
 
-        Ok(dissassemble_string)
+    /// Returns the options of the disassembly.
+    pub fn options(&self) -> DisassembleOptions {
+        DisassembleOptions {
+            is_script: self.is_script,
+            skip_private: self.skip_private,
+            skip_code: self.skip_code,
+            skip_locals: self.skip_locals,
+            skip_basic_blocks: self.skip_basic_blocks,
+        }
     }
 }
 
-impl CliCommand<Vec<String>> for CompilePackage {
+impl CliCommand<CompilePackageOutput> for CompilePackage {
     fn command_name(&self) -> &'static str {
         "CompilePackage"
     }
-    fn execute(self) -> CliTypedResult<Vec<String>> {
+
+    fn is_failure(output: &CompilePackageOutput) -> bool {
+        !output.success
+    }
+
+    fn execute(self) -> CliTypedResult<CompilePackageOutput> {
         set_bytecode_version(self.move_options.bytecode_version);
-        let build_options = BuildOptions {
-            install_dir: self.move_options.output_dir.clone(),
-            ..self
-                .included_artifacts_args
-                .included_artifacts
-                .build_options(
-                    self.move_options.skip_fetch_latest_git_deps,
-                    self.move_options.named_addresses(),
-                    self.move_options.bytecode_version_or_detault(),
-                )
+        let compilation = self.move_options.toolchain().compile(
+            &self.move_options.get_package_path()?,
+            self.included_artifacts_args.included_artifacts,
+        )?;
+        let pack = match compilation.package {
+            Some(pack) => pack,
+            // Compilation errors are part of the output, so they can be shown next to the code
+            None => {
+                return Ok(CompilePackageOutput {
+                    success: false,
+                    modules: vec![],
+                    diagnostics: compilation.diagnostics,
+                })
+            }
         };
-        let pack = BuiltPackage::build(self.move_options.get_package_path()?, build_options)
-            .map_err(|e| CliError::MoveCompilationError(format!("{:#}", e)))?;
         if self.save_metadata {
             pack.extract_metadata_and_save()?;
         }
-        
+
         let ids = pack
             .modules()
             .into_iter()
             .map(|m| m.self_id().to_string())
             .collect::<Vec<_>>();
 
-        Ok(ids)
+        Ok(CompilePackageOutput {
+            success: true,
+            modules: ids,
+            diagnostics: compilation.diagnostics,
+        })
     }
 }
@@ -0,0 +1,44 @@
+use clap::Parser;
+use move_core_types::account_address::AccountAddress;
+
+use crate::vm::PublishOutput;
+
+use super::types::{
+    account_address_wrapper::load_account_arg, cli_command::CliCommand,
+    move_package_dir::MovePackageDir, result::CliTypedResult, utils::set_bytecode_version,
+};
+use super::IncludedArtifactsArgs;
+
+/// Simulate publishing a package in a local VM
+///
+/// The dependencies of the package, which must include the Aptos framework, are loaded into
+/// an in-memory storage. The package is then published with `code::publish_package_txn`,
+/// the way a transaction of the sender would, including running `init_module` of every
+/// module. The resulting package registry of the sender is reported.
+#[derive(Parser)]
+pub struct PublishPackage {
+    /// Address of the account which publishes the package
+    #[clap(long, parse(try_from_str = load_account_arg))]
+    pub(crate) sender: AccountAddress,
+
+    #[clap(flatten)]
+    pub(crate) included_artifacts_args: IncludedArtifactsArgs,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<PublishOutput> for PublishPackage {
+    fn command_name(&self) -> &'static str {
+        "PublishPackage"
+    }
+
+    fn execute(self) -> CliTypedResult<PublishOutput> {
+        set_bytecode_version(self.move_options.bytecode_version);
+        Ok(self.move_options.toolchain().publish(
+            &self.move_options.get_package_path()?,
+            self.included_artifacts_args.included_artifacts,
+            self.sender,
+        )?)
+    }
+}
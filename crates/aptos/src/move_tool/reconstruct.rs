@@ -0,0 +1,59 @@
+use clap::Parser;
+use move_core_types::account_address::AccountAddress;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::framwork::ReconstructedPackage;
+use crate::toolchain::Toolchain;
+
+use super::types::{
+    account_address_wrapper::load_account_arg, cli_command::CliCommand, cli_error::CliError,
+    result::CliTypedResult, utils::parse_map,
+};
+
+/// Reconstruct a source package from its on-chain package metadata
+///
+/// The package and all its dependencies are written to the output directory, one directory
+/// per package, with dependencies in `Move.toml` rewritten to these local directories. The
+/// package can then be compiled again, e.g. to verify its bytecode.
+#[derive(Parser)]
+pub struct ReconstructPackage {
+    /// Address of the account the package is published under
+    #[clap(long, parse(try_from_str = load_account_arg))]
+    pub(crate) account: AccountAddress,
+
+    /// Name of the package
+    #[clap(long)]
+    pub(crate) package: String,
+
+    /// The BCS encoded `PackageRegistry` resource of every account involved, including the
+    /// accounts of all dependencies
+    ///
+    /// Example: 0x1=framework-registry.bcs,0xcafe=registry.bcs
+    #[clap(long, parse(try_from_str = parse_map))]
+    pub(crate) registries: BTreeMap<String, PathBuf>,
+
+    /// Directory to write the packages into
+    #[clap(long, parse(from_os_str))]
+    pub(crate) output_dir: PathBuf,
+}
+
+impl CliCommand<Vec<ReconstructedPackage>> for ReconstructPackage {
+    fn command_name(&self) -> &'static str {
+        "ReconstructPackage"
+    }
+
+    fn execute(self) -> CliTypedResult<Vec<ReconstructedPackage>> {
+        let mut registries = BTreeMap::new();
+        for (account, path) in &self.registries {
+            let bytes =
+                std::fs::read(path).map_err(|err| CliError::IO(path.display().to_string(), err))?;
+            registries.insert(load_account_arg(account)?, bytes);
+        }
+        Ok(Toolchain::default().reconstruct(
+            &registries,
+            self.account,
+            &self.package,
+            &self.output_dir,
+        )?)
+    }
+}
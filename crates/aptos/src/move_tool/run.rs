@@ -0,0 +1,114 @@
+use clap::Parser;
+
+use crate::toolchain::RunTarget;
+use crate::types::transaction::parse_transaction_argument;
+use crate::vm::ExecutionOutput;
+
+use super::types::{
+    account_address_wrapper::AccountAddressWrapper, cli_command::CliCommand, cli_error::CliError,
+    gas_options::GasOptions, member_id::MemberId, move_package_dir::MovePackageDir,
+    result::CliTypedResult, utils::set_bytecode_version,
+};
+use move_core_types::{language_storage::TypeTag, parser::parse_type_tag, value::MoveValue};
+
+/// Run a Move function or script of a package in a local VM
+///
+/// The package and all of its dependencies are loaded into an in-memory storage which
+/// starts out without any resources. The function is called regardless of its visibility,
+/// so the return values of non-entry functions can be inspected as well.
+#[derive(Parser)]
+pub struct RunFunction {
+    /// Function name as `<ADDRESS>::<MODULE_ID>::<FUNCTION_NAME>`
+    ///
+    /// Example: `0x1::message::set_message`
+    #[clap(long, required_unless_present = "script_name")]
+    pub(crate) function_id: Option<MemberId>,
+
+    /// Name of a script of the package to run instead of a function
+    #[clap(long, conflicts_with = "function_id")]
+    pub(crate) script_name: Option<String>,
+
+    /// TypeTag arguments separated by spaces.
+    ///
+    /// Example: `u8 u64 u128 bool address vector<u8> 0x1::aptos_coin::AptosCoin`
+    #[clap(long, multiple_values = true)]
+    pub(crate) type_args: Vec<String>,
+
+    /// Arguments in Move syntax separated by spaces.
+    ///
+    /// Example: `5u64 true @0x1 b"hello" x"0a0b"`
+    #[clap(long, multiple_values = true)]
+    pub(crate) args: Vec<String>,
+
+    /// Addresses of the signers, passed in front of all other arguments
+    #[clap(long, multiple_values = true)]
+    pub(crate) signers: Vec<AccountAddressWrapper>,
+
+    #[clap(flatten)]
+    pub(crate) gas_options: GasOptions,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl RunFunction {
+    fn type_args(&self) -> CliTypedResult<Vec<TypeTag>> {
+        self.type_args
+            .iter()
+            .map(|ty| {
+                parse_type_tag(ty)
+                    .map_err(|err| CliError::UnableToParse("--type-args", err.to_string()))
+            })
+            .collect()
+    }
+
+    fn args(&self) -> CliTypedResult<Vec<Vec<u8>>> {
+        let signers = self
+            .signers
+            .iter()
+            .map(|signer| MoveValue::Signer(signer.account_address));
+        let args = self
+            .args
+            .iter()
+            .map(|arg| {
+                parse_transaction_argument(arg)
+                    .map(MoveValue::from)
+                    .map_err(|err| CliError::UnableToParse("--args", err.to_string()))
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        signers
+            .chain(args.into_iter())
+            .map(|value| {
+                value.simple_serialize().ok_or_else(|| {
+                    CliError::UnexpectedError(format!("Unable to serialize argument {}", value))
+                })
+            })
+            .collect()
+    }
+}
+
+impl CliCommand<ExecutionOutput> for RunFunction {
+    fn command_name(&self) -> &'static str {
+        "RunFunction"
+    }
+
+    fn execute(self) -> CliTypedResult<ExecutionOutput> {
+        let ty_args = self.type_args()?;
+        let args = self.args()?;
+
+        set_bytecode_version(self.move_options.bytecode_version);
+        let target = match &self.function_id {
+            Some(function_id) => {
+                RunTarget::Function(&function_id.module_id, &function_id.member_id)
+            }
+            None => RunTarget::Script(self.script_name.as_deref().unwrap_or_default()),
+        };
+        Ok(self.move_options.toolchain().run(
+            &self.move_options.get_package_path()?,
+            target,
+            ty_args,
+            args,
+            self.gas_options.gas_schedule()?,
+        )?)
+    }
+}
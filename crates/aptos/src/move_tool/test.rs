@@ -0,0 +1,58 @@
+use clap::Parser;
+
+use crate::vm::TestSummary;
+
+use super::types::{
+    cli_command::CliCommand, gas_options::GasOptions, move_package_dir::MovePackageDir,
+    result::CliTypedResult, utils::set_bytecode_version,
+};
+
+const DEFAULT_INSTRUCTION_EXECUTION_BOUND: u64 = 100000;
+
+/// Run Move unit tests against a package path
+///
+/// The package is compiled in test mode and every `#[test]` function of the package is
+/// run in a local VM. The result of each test is reported individually.
+#[derive(Parser)]
+pub struct TestPackage {
+    /// A filter string to determine which unit tests to run
+    ///
+    /// Only tests whose name `<module>::<function>` contains the filter are run.
+    #[clap(long, short)]
+    pub filter: Option<String>,
+
+    /// The maximum number of instructions that can be executed by a test [default: 100000]
+    ///
+    /// If set, the number of instructions executed by one test will be bounded. With a gas
+    /// schedule, tests are bounded by its maximum gas instead.
+    #[clap(long = "instructions", short = 'i', conflicts_with = "gas_schedule")]
+    pub instruction_execution_bound: Option<u64>,
+
+    #[clap(flatten)]
+    pub(crate) gas_options: GasOptions,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<TestSummary> for TestPackage {
+    fn command_name(&self) -> &'static str {
+        "TestPackage"
+    }
+
+    fn is_failure(output: &TestSummary) -> bool {
+        !output.success
+    }
+
+    fn execute(self) -> CliTypedResult<TestSummary> {
+        let gas_schedule = self.gas_options.gas_schedule()?;
+        set_bytecode_version(self.move_options.bytecode_version);
+        Ok(self.move_options.toolchain().test(
+            &self.move_options.get_package_path()?,
+            self.filter.as_deref(),
+            self.instruction_execution_bound
+                .unwrap_or(DEFAULT_INSTRUCTION_EXECUTION_BOUND),
+            gas_schedule.as_ref(),
+        )?)
+    }
+}
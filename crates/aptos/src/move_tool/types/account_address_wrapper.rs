@@ -21,7 +21,6 @@ impl FromStr for AccountAddressWrapper {
 
 /// Loads an account arg and allows for naming based on profiles
 pub fn load_account_arg(str: &str) -> Result<AccountAddress, CliError> {
-    println!("load_account_arg, {}", str);
     if str.starts_with("0x") {
         AccountAddress::from_hex_literal(str).map_err(|err| {
             CliError::CommandArgumentError(format!("Failed to parse AccountAddress {}", err))
@@ -5,8 +5,8 @@ use async_trait::async_trait;
 use serde::Serialize;
 
 use super::{
-    result::{CliResult, CliTypedResult},
-    utils::{start_logger, to_common_result, to_common_success_result},
+    result::{CliResult, CliTypedResult, ResultWrapper},
+    utils::{start_logger, to_common_result, to_common_success_result, to_common_value},
 
 };
 
@@ -19,19 +19,32 @@ pub trait CliCommand<T: Serialize + Send>: Sized + Send {
     /// Executes the command, returning a command specific type
      fn execute(self) -> CliTypedResult<T>;
 
+    /// Whether an output reports that the command failed, e.g. failed unit tests or a package
+    /// which did not compile. Such an output is still serialized as the result, but returned
+    /// as an error, so the process exits with an error code.
+     fn is_failure(_output: &T) -> bool {
+        false
+    }
+
     /// Executes the command, and serializes it to the common JSON output type
      fn execute_serialized(self) -> CliResult {
         let command_name = self.command_name();
         start_logger();
         let start_time = Instant::now();
-        to_common_result(command_name, start_time, self.execute())
+        to_failable_result(command_name, start_time, self.execute(), Self::is_failure)
     }
 
     /// Same as execute serialized without setting up logging
      fn execute_serialized_without_logger(self) -> CliResult {
         let command_name = self.command_name();
         let start_time = Instant::now();
-        to_common_result(command_name, start_time, self.execute())
+        to_failable_result(command_name, start_time, self.execute(), Self::is_failure)
+    }
+
+    /// Executes the command, and returns the common output type as a JSON value
+     fn execute_value(self) -> ResultWrapper<serde_json::Value> {
+        start_logger();
+        to_common_value(self.execute())
     }
 
     /// Executes the command, and throws away Ok(result) for the string Success
@@ -42,3 +55,18 @@ pub trait CliCommand<T: Serialize + Send>: Sized + Send {
         to_common_success_result(command_name, start_time, self.execute())
     }
 }
+
+/// Serializes the result like `to_common_result`, but returns it as an error if the output
+/// reports a failure.
+fn to_failable_result<T: Serialize>(
+    command: &str,
+    start_time: Instant,
+    result: CliTypedResult<T>,
+    is_failure: fn(&T) -> bool,
+) -> CliResult {
+    let failed = matches!(&result, Ok(output) if is_failure(output));
+    match to_common_result(command, start_time, result) {
+        Ok(output) if failed => Err(output),
+        output => output,
+    }
+}
@@ -1,3 +1,4 @@
+use crate::toolchain::ToolchainError;
 use thiserror::Error;
 
 /// CLI Errors for reporting through telemetry and outputs
@@ -108,6 +109,18 @@ impl From<anyhow::Error> for CliError {
     }
 }
 
+impl From<ToolchainError> for CliError {
+    fn from(e: ToolchainError) -> Self {
+        match e {
+            ToolchainError::InvalidArgument(msg) => CliError::CommandArgumentError(msg),
+            ToolchainError::IO(path, err) => CliError::IO(path, err),
+            ToolchainError::MoveCompilationError(msg) => CliError::MoveCompilationError(msg),
+            ToolchainError::UnableToParse(what, msg) => CliError::UnableToParse(what, msg),
+            ToolchainError::UnexpectedError(msg) => CliError::UnexpectedError(msg),
+        }
+    }
+}
+
 impl From<bcs::Error> for CliError {
     fn from(e: bcs::Error) -> Self {
         CliError::UnexpectedError(e.to_string())
@@ -0,0 +1,33 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use super::result::CliTypedResult;
+use crate::vm::GasSchedule;
+
+/// Options for metering the gas consumed by local execution
+#[derive(Debug, Default, Parser)]
+pub struct GasOptions {
+    /// Path to a JSON gas schedule
+    ///
+    /// If set, the gas consumed by the execution is metered according to the schedule and
+    /// a gas profile is included in the output.
+    #[clap(long, parse(from_os_str))]
+    pub gas_schedule: Option<PathBuf>,
+
+    /// The maximum gas of a single execution, overriding the one of the gas schedule
+    #[clap(long, requires = "gas_schedule")]
+    pub max_gas: Option<u64>,
+}
+
+impl GasOptions {
+    pub fn gas_schedule(&self) -> CliTypedResult<Option<GasSchedule>> {
+        let mut schedule = match &self.gas_schedule {
+            Some(path) => GasSchedule::from_file(path)?,
+            None => return Ok(None),
+        };
+        if let Some(max_gas) = self.max_gas {
+            schedule.max_gas = max_gas;
+        }
+        Ok(Some(schedule))
+    }
+}
@@ -0,0 +1,28 @@
+use std::str::FromStr;
+
+use super::cli_error::CliError;
+use crate::toolchain::parse_function_id;
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+/// Identifier of a module member (e.g. a function), written as `<address>::<module>::<member>`
+#[derive(Clone, Debug)]
+pub struct MemberId {
+    pub module_id: ModuleId,
+    pub member_id: Identifier,
+}
+
+fn parse_member_id(function_id: &str) -> Result<MemberId, CliError> {
+    let (module_id, member_id) = parse_function_id(function_id)?;
+    Ok(MemberId {
+        module_id,
+        member_id,
+    })
+}
+
+impl FromStr for MemberId {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_member_id(s)
+    }
+}
@@ -4,4 +4,6 @@ pub mod move_package_dir;
 pub mod account_address_wrapper;
 pub mod utils;
 pub mod result;
-pub mod cli_command;
\ No newline at end of file
+pub mod cli_command;
+pub mod member_id;
+pub mod gas_options;
\ No newline at end of file
@@ -10,6 +10,7 @@ use super::{
     utils::dir_default_to_current,
 };
 use move_core_types::account_address::AccountAddress;
+use crate::toolchain::Toolchain;
 use super::account_address_wrapper::AccountAddressWrapper;
 
 /// Options for compiling a move package dir
@@ -68,6 +69,16 @@ impl MovePackageDir {
             .collect()
     }
 
+    /// Returns the toolchain configured with these options.
+    pub fn toolchain(&self) -> Toolchain {
+        Toolchain {
+            named_addresses: self.named_addresses(),
+            bytecode_version: self.bytecode_version_or_detault(),
+            skip_fetch_latest_git_deps: self.skip_fetch_latest_git_deps,
+            install_dir: self.output_dir.clone(),
+        }
+    }
+
     pub fn bytecode_version_or_detault(&self) -> u32 {
         self.bytecode_version.unwrap_or(5)
     }
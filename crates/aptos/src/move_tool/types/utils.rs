@@ -91,6 +91,18 @@ pub fn to_common_result<T: Serialize>(
     }
 }
 
+/// Converts a result into a `ResultWrapper` holding its JSON value, for embedding command
+/// results into other JSON documents
+pub fn to_common_value<T: Serialize>(
+    result: CliTypedResult<T>,
+) -> ResultWrapper<serde_json::Value> {
+    result
+        .and_then(|inner| {
+            serde_json::to_value(inner).map_err(|err| CliError::UnexpectedError(err.to_string()))
+        })
+        .into()
+}
+
 /// Convert any successful response to Success
 pub fn to_common_success_result<T>(
         command: &str,
@@ -0,0 +1,62 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::framwork::PackageVerification;
+
+use super::types::{
+    cli_command::CliCommand, cli_error::CliError, move_package_dir::MovePackageDir,
+    result::CliTypedResult, utils::set_bytecode_version,
+};
+
+/// Verify that a published package was built from a local package
+///
+/// The local package is compiled and its modules are compared byte for byte with the
+/// published bytecode. The source digest of the local build is compared with the one in the
+/// published package metadata.
+#[derive(Parser)]
+pub struct VerifyPackage {
+    /// Path to the BCS encoded `PackageMetadata` of the published package
+    #[clap(long, parse(from_os_str))]
+    pub(crate) metadata_file: PathBuf,
+
+    /// Paths to the published bytecode of the modules, separated by spaces
+    #[clap(long, parse(from_os_str), multiple_values = true)]
+    pub(crate) modules: Vec<PathBuf>,
+
+    /// Accept modules which differ only in the Aptos runtime metadata
+    #[clap(long)]
+    pub(crate) ignore_metadata: bool,
+
+    #[clap(flatten)]
+    pub(crate) move_options: MovePackageDir,
+}
+
+impl CliCommand<PackageVerification> for VerifyPackage {
+    fn command_name(&self) -> &'static str {
+        "VerifyPackage"
+    }
+
+    fn is_failure(output: &PackageVerification) -> bool {
+        !output.verified
+    }
+
+    fn execute(self) -> CliTypedResult<PackageVerification> {
+        let read = |path: &PathBuf| {
+            std::fs::read(path).map_err(|err| CliError::IO(path.display().to_string(), err))
+        };
+        let metadata = read(&self.metadata_file)?;
+        let published_modules = self
+            .modules
+            .iter()
+            .map(read)
+            .collect::<CliTypedResult<Vec<_>>>()?;
+
+        set_bytecode_version(self.move_options.bytecode_version);
+        Ok(self.move_options.toolchain().verify_package(
+            &self.move_options.get_package_path()?,
+            &metadata,
+            &published_modules,
+            self.ignore_metadata,
+        )?)
+    }
+}
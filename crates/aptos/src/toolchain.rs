@@ -0,0 +1,682 @@
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::{IdentStr, Identifier};
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::framwork::docgen::DocgenOptions;
+use crate::framwork::{
+    check_upgrade, disassemble, disassemble_annotated, disassemble_module, generate_ts_bindings,
+    parameters_from_abi, parameters_from_module, verify_package, ArgumentParser, BuildOptions,
+    BuiltPackage, CompatibilityReport, CompiledPackageOutput, Diagnostic, DiagnosticsCollector,
+    DisassembleOptions, DisassemblyContext, DisassemblyOutput, IncludedArtifacts,
+    ModuleDisassembly, PackageMetadataView, PackageRegistries, PackageVerification,
+    ReconstructedPackage, StoredPackage, VirtualPackage,
+};
+use crate::types::transaction::{EntryFunction, TransactionPayload};
+use crate::vm::{
+    run_unit_tests, ExecutionOutput, GasSchedule, LocalExecutor, PublishOutput, TestSummary,
+};
+
+/// The operations of the tool as a library, for embedding it into other programs.
+///
+/// The commands of the CLI and the JavaScript bindings are implemented on top of the toolchain.
+/// Unlike the commands, its methods take all inputs as arguments, return typed results and
+/// leave process-global state alone. In particular, the bytecode version is passed to the
+/// package system explicitly rather than through the `MOVE_BYTECODE_VERSION` environment
+/// variable, so it applies to all returned bytecode, but not to the artifacts which the package
+/// system writes into the build directory.
+///
+/// ```ignore
+/// let toolchain = Toolchain {
+///     bytecode_version: 6,
+///     ..Toolchain::default()
+/// };
+/// let compilation = toolchain.compile(Path::new("hello"), IncludedArtifacts::Sparse)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Toolchain {
+    /// Named addresses in addition to the ones assigned by the packages
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// The version of the emitted bytecode
+    pub bytecode_version: u32,
+    /// Skip pulling the latest git dependencies
+    pub skip_fetch_latest_git_deps: bool,
+    /// Directory to save compiled packages in, defaults to `<package>/build`
+    pub install_dir: Option<PathBuf>,
+}
+
+impl Default for Toolchain {
+    fn default() -> Self {
+        Self {
+            named_addresses: BTreeMap::new(),
+            bytecode_version: 5,
+            skip_fetch_latest_git_deps: false,
+            install_dir: None,
+        }
+    }
+}
+
+/// The errors of the toolchain. The CLI reports them as the `CliError` of the same name.
+#[derive(Debug, Error)]
+pub enum ToolchainError {
+    #[error("Invalid arguments: {0}")]
+    InvalidArgument(String),
+    #[error("Error accessing '{0}': {1}")]
+    IO(String, #[source] std::io::Error),
+    #[error("Move compilation failed: {0}")]
+    MoveCompilationError(String),
+    #[error("Unable to parse '{0}': error: {1}")]
+    UnableToParse(&'static str, String),
+    #[error("Unexpected error: {0}")]
+    UnexpectedError(String),
+}
+
+pub type ToolchainResult<T> = Result<T, ToolchainError>;
+
+/// The result of compiling a package.
+pub struct Compilation {
+    /// The package, if it compiled
+    pub package: Option<BuiltPackage>,
+    /// The errors and warnings of the compiler and of the extended checks
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The arguments of an entry function call, without the signers.
+pub enum PayloadArguments {
+    /// Arguments as given on the command line, see `ArgumentParser::parse_str_value`
+    Strings(Vec<String>),
+    /// Arguments as JSON values, see `ArgumentParser::parse_value`
+    Json(Vec<serde_json::Value>),
+}
+
+impl PayloadArguments {
+    fn len(&self) -> usize {
+        match self {
+            PayloadArguments::Strings(args) => args.len(),
+            PayloadArguments::Json(args) => args.len(),
+        }
+    }
+}
+
+/// What to run in a local VM.
+pub enum RunTarget<'a> {
+    /// A function of the package or of one of its dependencies, regardless of its visibility
+    Function(&'a ModuleId, &'a IdentStr),
+    /// A script of the package, by name
+    Script(&'a str),
+}
+
+/// Bytecode to disassemble, together with the source and the source map which go with it.
+pub struct DisassemblyInput {
+    pub bytecode: Vec<u8>,
+    /// Whether the bytecode is known to be a script
+    pub is_script: bool,
+    pub context: DisassemblyContext,
+}
+
+/// A transaction payload which calls an entry function.
+#[derive(Debug, Serialize)]
+pub struct BuildPayloadOutput {
+    /// The BCS encoded `TransactionPayload`, as hex
+    pub bcs: String,
+    /// The payload in the JSON format of the Aptos REST API
+    pub json: serde_json::Value,
+    /// Where the types of the arguments were taken from, either `abi` or `bytecode`
+    pub checked_against: &'static str,
+    /// The BCS encoded `TransactionPayload`
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+}
+
+/// Where to find the declaration of a function.
+pub enum FunctionSource<'a> {
+    /// The bytecode of the module which declares the function
+    Module(&'a [u8]),
+    /// A package which is built, and whose ABIs, or modules including the ones of its
+    /// dependencies, declare the function
+    Package(&'a Path),
+}
+
+impl Toolchain {
+    /// Returns the options to build a package with the given artifacts.
+    pub fn build_options(&self, included_artifacts: IncludedArtifacts) -> BuildOptions {
+        BuildOptions {
+            install_dir: self.install_dir.clone(),
+            ..included_artifacts.build_options(
+                self.skip_fetch_latest_git_deps,
+                self.named_addresses.clone(),
+                self.bytecode_version,
+            )
+        }
+    }
+
+    /// Compiles the package at `package_path`. A package which fails to compile is not an
+    /// error; the compilation then has no package, and the diagnostics contain the errors.
+    pub fn compile(
+        &self,
+        package_path: &Path,
+        included_artifacts: IncludedArtifacts,
+    ) -> ToolchainResult<Compilation> {
+        let mut diagnostics = DiagnosticsCollector::new();
+        match BuiltPackage::build_with_diagnostics(
+            package_path.to_path_buf(),
+            self.build_options(included_artifacts),
+            &mut diagnostics,
+        ) {
+            Ok(package) => Ok(Compilation {
+                package: Some(package),
+                diagnostics: diagnostics.into_inner(),
+            }),
+            Err(_) if diagnostics.has_errors() => Ok(Compilation {
+                package: None,
+                diagnostics: diagnostics.into_inner(),
+            }),
+            Err(e) => Err(ToolchainError::MoveCompilationError(format!("{:#}", e))),
+        }
+    }
+
+    /// Compiles a package given as file contents, see `VirtualPackage::compile`.
+    ///
+    /// The package system only reads packages from a file system, so the files are staged
+    /// below `scratch_dir`. On wasm32-wasi it has to be a preopened directory.
+    pub fn compile_files(
+        &self,
+        package: &VirtualPackage,
+        included_artifacts: IncludedArtifacts,
+        scratch_dir: &Path,
+    ) -> ToolchainResult<CompiledPackageOutput> {
+        package
+            .compile(self.build_options(included_artifacts), scratch_dir)
+            .map_err(|e| ToolchainError::MoveCompilationError(format!("{:#}", e)))
+    }
+
+    /// Disassembles the bytecode of a module or script.
+    pub fn disassemble(
+        &self,
+        bytecode: &[u8],
+        options: &DisassembleOptions,
+        context: DisassemblyContext,
+    ) -> ToolchainResult<DisassemblyOutput> {
+        disassemble(bytecode, options, context)
+            .map_err(|err| ToolchainError::UnableToParse("bytecode", format!("{:#}", err)))
+    }
+
+    /// Disassembles the bytecode of a module into a structured form, see `disassemble_module`.
+    pub fn disassemble_module(
+        &self,
+        bytecode: &[u8],
+        options: &DisassembleOptions,
+        context: &DisassemblyContext,
+    ) -> ToolchainResult<ModuleDisassembly> {
+        disassemble_module(bytecode, options, context)
+            .map_err(|err| ToolchainError::UnableToParse("bytecode", format!("{:#}", err)))
+    }
+
+    /// Disassembles the bytecode of a module or script into its source, annotated with the
+    /// bytecode, see `disassemble_annotated`. The context must have the source and source map.
+    pub fn disassemble_annotated(
+        &self,
+        bytecode: &[u8],
+        options: &DisassembleOptions,
+        context: &DisassemblyContext,
+    ) -> ToolchainResult<String> {
+        disassemble_annotated(bytecode, options, context)
+            .map_err(|err| ToolchainError::UnableToParse("bytecode", format!("{:#}", err)))
+    }
+
+    /// Builds the package at `package_path` and returns the bytecode of its module or script
+    /// `name`, with the source and source map of the package.
+    pub fn package_unit(
+        &self,
+        package_path: &Path,
+        name: &str,
+    ) -> ToolchainResult<DisassemblyInput> {
+        let pack = self.build(package_path, self.build_options(IncludedArtifacts::None))?;
+        let unit = pack.extract_unit(name).ok_or_else(|| {
+            ToolchainError::InvalidArgument(format!(
+                "Package '{}' has no module or script `{}`",
+                pack.name(),
+                name
+            ))
+        })?;
+        let source_path = unit.source_path.display().to_string();
+        let source = std::fs::read_to_string(&unit.source_path)
+            .map_err(|err| ToolchainError::IO(source_path.clone(), err))?;
+        Ok(DisassemblyInput {
+            bytecode: unit.bytecode,
+            is_script: unit.is_script,
+            context: DisassemblyContext {
+                source: Some((source_path, source)),
+                source_map: Some(unit.source_map),
+                coverage: None,
+            },
+        })
+    }
+
+    /// Pairs the bytecode of a module with its source and source map, as far as they are
+    /// included in the BCS of the metadata of its package.
+    pub fn module_with_metadata(
+        &self,
+        bytecode: Vec<u8>,
+        metadata: &[u8],
+    ) -> ToolchainResult<DisassemblyInput> {
+        let module = CompiledModule::deserialize(&bytecode)
+            .map_err(|err| ToolchainError::UnableToParse("module bytecode", err.to_string()))?;
+        let name = module.self_id().name().to_string();
+        let package = load_metadata(metadata)?;
+        let module_metadata = package.module(&name).ok_or_else(|| {
+            ToolchainError::InvalidArgument(format!(
+                "The metadata of package '{}' has no module `{}`",
+                package.name(),
+                name
+            ))
+        })?;
+        let source = StoredPackage::module_source(module_metadata)
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))?;
+        let source_map = StoredPackage::module_source_map(module_metadata)
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))?;
+        Ok(DisassemblyInput {
+            bytecode,
+            is_script: false,
+            context: DisassemblyContext {
+                source: source.map(|source| (format!("sources/{}.move", name), source)),
+                source_map,
+                coverage: None,
+            },
+        })
+    }
+
+    /// Decodes the BCS of package metadata, with the manifest, sources and source maps
+    /// decompressed.
+    pub fn decode_metadata(&self, bytes: &[u8]) -> ToolchainResult<PackageMetadataView> {
+        load_metadata(bytes)?
+            .to_view()
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))
+    }
+
+    /// Writes the manifest and the module sources contained in the BCS of package metadata
+    /// into `dir`, and returns the paths of the written files.
+    pub fn extract_metadata_sources(
+        &self,
+        bytes: &[u8],
+        dir: &Path,
+    ) -> ToolchainResult<Vec<PathBuf>> {
+        load_metadata(bytes)?
+            .save_sources(dir)
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))
+    }
+
+    /// Builds the payload of a transaction which calls an entry function. The arguments are
+    /// checked against the ABI of the function, or against its signature in the compiled
+    /// module if there is no ABI, and BCS encoded.
+    pub fn build_payload(
+        &self,
+        module_id: &ModuleId,
+        function: &IdentStr,
+        ty_args: Vec<TypeTag>,
+        args: &PayloadArguments,
+        source: FunctionSource,
+    ) -> ToolchainResult<BuildPayloadOutput> {
+        let parameters = self.parameters(module_id, function, &ty_args, source)?;
+        if args.len() != parameters.types.len() {
+            return Err(ToolchainError::InvalidArgument(format!(
+                "Expected {} arguments, but {} were given",
+                parameters.types.len(),
+                args.len()
+            )));
+        }
+
+        let parser = ArgumentParser::new(parameters.named_addresses);
+        let values = parameters
+            .types
+            .iter()
+            .enumerate()
+            .map(|(idx, ty)| {
+                let name = format!("arg{}", idx);
+                match args {
+                    PayloadArguments::Json(args) => parser.parse_value(&name, ty, &args[idx]),
+                    PayloadArguments::Strings(args) => {
+                        parser.parse_str_value(&name, ty, &args[idx])
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| ToolchainError::InvalidArgument(err.to_string()))?;
+
+        let json = json!({
+            "type": "entry_function_payload",
+            "function": format!(
+                "{}::{}::{}",
+                module_id.address().to_hex_literal(),
+                module_id.name(),
+                function
+            ),
+            "type_arguments": ty_args.iter().map(|ty| ty.to_string()).collect::<Vec<_>>(),
+            "arguments": values
+                .iter()
+                .zip(&parameters.types)
+                .map(|(value, ty)| ArgumentParser::to_json(value, ty))
+                .collect::<Vec<_>>(),
+        });
+        let args = values
+            .into_iter()
+            .map(|value| {
+                value.simple_serialize().ok_or_else(|| {
+                    ToolchainError::UnexpectedError(format!("Unable to serialize {}", value))
+                })
+            })
+            .collect::<ToolchainResult<Vec<_>>>()?;
+        let entry_function =
+            EntryFunction::new(module_id.clone(), function.to_owned(), ty_args, args);
+        let bytes = bcs::to_bytes(&TransactionPayload::EntryFunction(entry_function))
+            .map_err(|err| ToolchainError::UnexpectedError(err.to_string()))?;
+
+        Ok(BuildPayloadOutput {
+            bcs: format!("0x{}", hex::encode(&bytes)),
+            json,
+            checked_against: parameters.checked_against,
+            bytes,
+        })
+    }
+
+    /// Builds the package at `package_path` and runs a function or script of it in a local VM,
+    /// into which the package and all of its dependencies are loaded. `args` are the BCS
+    /// encoded arguments, including the signers.
+    pub fn run(
+        &self,
+        package_path: &Path,
+        target: RunTarget,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+        gas_schedule: Option<GasSchedule>,
+    ) -> ToolchainResult<ExecutionOutput> {
+        let pack = self.build(package_path, self.build_options(IncludedArtifacts::None))?;
+        let mut executor = LocalExecutor::new()?;
+        executor.load_package(&pack);
+        if let Some(gas_schedule) = gas_schedule {
+            executor.set_gas_schedule(gas_schedule);
+        }
+
+        match target {
+            RunTarget::Function(module_id, function) => {
+                Ok(executor.execute_function(module_id, function, ty_args, args))
+            }
+            RunTarget::Script(name) => {
+                let script = pack.extract_script_code_by_name(name).ok_or_else(|| {
+                    ToolchainError::InvalidArgument(format!(
+                        "Script '{}' not found in package '{}'",
+                        name,
+                        pack.name()
+                    ))
+                })?;
+                Ok(executor.execute_script(script, ty_args, args))
+            }
+        }
+    }
+
+    /// Builds the package at `package_path` in test mode and runs its unit tests whose name
+    /// `<module>::<function>` contains `filter`, see `run_unit_tests`.
+    pub fn test(
+        &self,
+        package_path: &Path,
+        filter: Option<&str>,
+        instruction_limit: u64,
+        gas_schedule: Option<&GasSchedule>,
+    ) -> ToolchainResult<TestSummary> {
+        let (pack, test_plan) = BuiltPackage::build_for_test(
+            package_path.to_path_buf(),
+            self.build_options(IncludedArtifacts::None),
+        )
+        .map_err(|e| ToolchainError::MoveCompilationError(format!("{:#}", e)))?;
+        run_unit_tests(&pack, &test_plan, filter, instruction_limit, gas_schedule)
+    }
+
+    /// Builds the package at `package_path` and publishes it as `sender` in a local VM, into
+    /// which its dependencies are loaded.
+    pub fn publish(
+        &self,
+        package_path: &Path,
+        included_artifacts: IncludedArtifacts,
+        sender: AccountAddress,
+    ) -> ToolchainResult<PublishOutput> {
+        let pack = self.build(package_path, self.build_options(included_artifacts))?;
+        let metadata = pack
+            .extract_metadata()
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))?;
+
+        let mut executor = LocalExecutor::new()?;
+        executor.load_dependencies(&pack);
+        Ok(executor.publish_package(sender, &metadata, pack.extract_code()))
+    }
+
+    /// Generates the documentation of the package at `package_path` and of its dependencies,
+    /// and returns the paths of the written files, see `DocgenOptions::document_package`.
+    pub fn document(
+        &self,
+        package_path: &Path,
+        options: &DocgenOptions,
+    ) -> ToolchainResult<Vec<PathBuf>> {
+        options
+            .document_package(package_path, self.named_addresses.clone())
+            .map_err(|e| ToolchainError::MoveCompilationError(format!("{:#}", e)))
+    }
+
+    /// Builds the package at `package_path` and verifies that it is the published package
+    /// with the given BCS of its metadata and bytecode of its modules, see `verify_package`.
+    pub fn verify_package(
+        &self,
+        package_path: &Path,
+        metadata: &[u8],
+        published_modules: &[Vec<u8>],
+        ignore_metadata: bool,
+    ) -> ToolchainResult<PackageVerification> {
+        let stored = load_metadata(metadata)?;
+        let pack = self.build(package_path, self.build_options(IncludedArtifacts::None))?;
+        verify_package(&pack, stored.metadata(), published_modules, ignore_metadata)
+            .map_err(|err| ToolchainError::UnableToParse("module bytecode", format!("{:#}", err)))
+    }
+
+    /// Builds the package at `package_path` and checks whether it may upgrade the published
+    /// package with the given BCS of its metadata and bytecode of its modules, see
+    /// `check_upgrade`.
+    pub fn check_compatibility(
+        &self,
+        package_path: &Path,
+        old_metadata: &[u8],
+        old_modules: &[Vec<u8>],
+        check_friends: bool,
+    ) -> ToolchainResult<CompatibilityReport> {
+        let stored = load_metadata(old_metadata)?;
+        let old_modules = old_modules
+            .iter()
+            .enumerate()
+            .map(|(idx, bytes)| {
+                CompiledModule::deserialize(bytes).map_err(|err| {
+                    ToolchainError::UnableToParse("module bytecode", format!("#{}: {}", idx, err))
+                })
+            })
+            .collect::<ToolchainResult<Vec<_>>>()?;
+
+        let pack = self.build(package_path, self.build_options(IncludedArtifacts::None))?;
+        let new_policy = pack
+            .extract_metadata()
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))?
+            .upgrade_policy;
+        let new_modules = pack.modules().cloned().collect::<Vec<_>>();
+
+        Ok(check_upgrade(
+            stored.name(),
+            stored.metadata().upgrade_policy,
+            &old_modules,
+            new_policy,
+            &new_modules,
+            check_friends,
+        ))
+    }
+
+    /// Reconstructs the source package `name` published under `account` into `output_dir`,
+    /// see `PackageRegistries::reconstruct`. `registries` holds the BCS encoded
+    /// `PackageRegistry` of every account involved, including the accounts of all
+    /// dependencies.
+    pub fn reconstruct(
+        &self,
+        registries: &BTreeMap<AccountAddress, Vec<u8>>,
+        account: AccountAddress,
+        name: &str,
+        output_dir: &Path,
+    ) -> ToolchainResult<Vec<ReconstructedPackage>> {
+        let mut package_registries = PackageRegistries::new();
+        for (address, bytes) in registries {
+            package_registries
+                .add_registry_bcs(*address, bytes)
+                .map_err(|err| {
+                    ToolchainError::UnableToParse("PackageRegistry", format!("{:#}", err))
+                })?;
+        }
+        package_registries
+            .reconstruct(account, name, output_dir)
+            .map_err(|err| ToolchainError::UnexpectedError(format!("{:#}", err)))
+    }
+
+    /// Builds the package at `package_path` and generates TypeScript bindings for its entry
+    /// and view functions, see `generate_ts_bindings`. Returns the contents of the files by
+    /// file name.
+    pub fn generate_ts_bindings(
+        &self,
+        package_path: &Path,
+    ) -> ToolchainResult<BTreeMap<String, String>> {
+        let build_options = BuildOptions {
+            with_abis: true,
+            ..self.build_options(IncludedArtifacts::None)
+        };
+        let pack = self.build(package_path, build_options)?;
+        let abis = pack.extract_abis().unwrap_or_default();
+        let modules = pack.modules().collect::<Vec<_>>();
+        Ok(generate_ts_bindings(&abis, &modules))
+    }
+
+    fn build(
+        &self,
+        package_path: &Path,
+        build_options: BuildOptions,
+    ) -> ToolchainResult<BuiltPackage> {
+        BuiltPackage::build(package_path.to_path_buf(), build_options)
+            .map_err(|e| ToolchainError::MoveCompilationError(format!("{:#}", e)))
+    }
+
+    fn parameters(
+        &self,
+        module_id: &ModuleId,
+        function: &IdentStr,
+        ty_args: &[TypeTag],
+        source: FunctionSource,
+    ) -> ToolchainResult<Parameters> {
+        let from_module = |module: &CompiledModule, named_addresses| {
+            parameters_from_module(module, function, ty_args)
+                .map(|types| Parameters {
+                    types,
+                    checked_against: "bytecode",
+                    named_addresses,
+                })
+                .map_err(|err| ToolchainError::InvalidArgument(format!("{:#}", err)))
+        };
+
+        let package_path = match source {
+            FunctionSource::Module(bytes) => {
+                let module = CompiledModule::deserialize(bytes).map_err(|err| {
+                    ToolchainError::UnableToParse("module bytecode", err.to_string())
+                })?;
+                if module.self_id() != *module_id {
+                    return Err(ToolchainError::InvalidArgument(format!(
+                        "The bytecode is module `{}`, not `{}`",
+                        module.self_id(),
+                        module_id
+                    )));
+                }
+                return from_module(&module, self.named_addresses.clone());
+            }
+            FunctionSource::Package(package_path) => package_path,
+        };
+
+        let build_options = BuildOptions {
+            with_abis: true,
+            ..self.build_options(IncludedArtifacts::None)
+        };
+        let pack = self.build(package_path, build_options)?;
+
+        let named_addresses = pack.named_addresses();
+        let abis = pack.extract_abis().unwrap_or_default();
+        if let Some(params) = parameters_from_abi(&abis, module_id, function, ty_args) {
+            return params
+                .map(|types| Parameters {
+                    types,
+                    checked_against: "abi",
+                    named_addresses,
+                })
+                .map_err(|err| ToolchainError::InvalidArgument(format!("{:#}", err)));
+        }
+        // The ABIs only cover entry functions of the package itself
+        let (_, bytes) = pack
+            .extract_all_code()
+            .into_iter()
+            .find(|(id, _)| id == module_id)
+            .ok_or_else(|| {
+                ToolchainError::InvalidArgument(format!(
+                    "Module `{}` is neither part of package '{}' nor of its dependencies",
+                    module_id,
+                    pack.name()
+                ))
+            })?;
+        let module = CompiledModule::deserialize(&bytes)
+            .map_err(|err| ToolchainError::UnableToParse("module bytecode", err.to_string()))?;
+        from_module(&module, named_addresses)
+    }
+}
+
+/// The types of the parameters of a function, where they were taken from, and the named
+/// addresses which can be used in the arguments.
+struct Parameters {
+    types: Vec<TypeTag>,
+    checked_against: &'static str,
+    named_addresses: BTreeMap<String, AccountAddress>,
+}
+
+/// Parses an account address, as hex with or without the `0x` prefix.
+pub fn parse_address(address: &str) -> ToolchainResult<AccountAddress> {
+    let parsed = if address.starts_with("0x") {
+        AccountAddress::from_hex_literal(address).map_err(|err| err.to_string())
+    } else {
+        AccountAddress::from_str(address).map_err(|err| err.to_string())
+    };
+    parsed.map_err(|err| {
+        ToolchainError::InvalidArgument(format!("Failed to parse AccountAddress {}", err))
+    })
+}
+
+/// Parses the id of a function, written as `<address>::<module>::<function>`.
+pub fn parse_function_id(function_id: &str) -> ToolchainResult<(ModuleId, Identifier)> {
+    let ids: Vec<&str> = function_id.split_terminator("::").collect();
+    if ids.len() != 3 {
+        return Err(ToolchainError::InvalidArgument(
+            "FunctionId is not well formed.  Must be of the form <address>::<module>::<function>"
+                .to_string(),
+        ));
+    }
+    let address = parse_address(ids[0])?;
+    let module = Identifier::from_str(ids[1])
+        .map_err(|err| ToolchainError::UnableToParse("Module Name", err.to_string()))?;
+    let function = Identifier::from_str(ids[2])
+        .map_err(|err| ToolchainError::UnableToParse("Member Name", err.to_string()))?;
+    Ok((ModuleId::new(address, module), function))
+}
+
+fn load_metadata(bytes: &[u8]) -> ToolchainResult<StoredPackage> {
+    StoredPackage::from_bcs(bytes)
+        .map_err(|err| ToolchainError::UnableToParse("PackageMetadata", format!("{:#}", err)))
+}
@@ -1,9 +1,12 @@
 mod module;
 mod abort_info;
+mod payload;
 
 mod script;
 mod transaction_argument;
 
 pub use module::{Module, ModuleBundle};
 pub use abort_info::AbortInfo;
-pub use script::{EntryABI};
\ No newline at end of file
+pub use payload::TransactionPayload;
+pub use script::{ArgumentABI, EntryABI, EntryFunction, EntryFunctionABI, Script};
+pub use transaction_argument::{parse_transaction_argument, TransactionArgument};
\ No newline at end of file
@@ -0,0 +1,25 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::types::transaction::{EntryFunction, ModuleBundle, Script};
+use serde::{Deserialize, Serialize};
+
+/// Different kinds of transactions.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TransactionPayload {
+    /// A transaction that executes code.
+    Script(Script),
+    /// A transaction that publishes multiple modules at the same time.
+    ModuleBundle(ModuleBundle),
+    /// A transaction that executes an existing entry function published on-chain.
+    EntryFunction(EntryFunction),
+}
+
+impl TransactionPayload {
+    pub fn into_entry_function(self) -> Option<EntryFunction> {
+        match self {
+            Self::EntryFunction(f) => Some(f),
+            _ => None,
+        }
+    }
+}
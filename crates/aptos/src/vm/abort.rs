@@ -0,0 +1,182 @@
+use crate::framwork::{get_vm_metadata, RuntimeModuleMetadataV1};
+use move_binary_format::errors::{Location, VMError};
+use move_core_types::language_storage::ModuleId;
+use move_core_types::vm_status::{AbortLocation, StatusCode, VMStatus};
+use move_vm_runtime::move_vm::MoveVM;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// An abort of Move code, explained with the error map of the aborting module.
+#[derive(Debug, Clone, Serialize)]
+pub struct AbortExplanation {
+    /// The id of the aborting module, or `script`
+    pub module: String,
+    pub code: u64,
+    /// The category of the code according to the conventions of `0x1::error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<&'static str>,
+    /// The name of the error constant, if the error map of the module has an entry for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason_name: Option<String>,
+    /// The doc comment of the error constant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Explains a `MoveAbort` status, or returns `None` for any other status.
+///
+/// `metadata` holds the runtime metadata of modules whose bytecode carries none, such as the
+/// modules of dependencies, see `BuiltPackage::dependency_metadata`.
+pub fn explain_vm_status(
+    vm: &MoveVM,
+    metadata: &BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+    status: &VMStatus,
+) -> Option<AbortExplanation> {
+    match status {
+        VMStatus::MoveAbort(AbortLocation::Module(module_id), code) => {
+            Some(explain_abort(vm, metadata, Some(module_id), *code))
+        }
+        VMStatus::MoveAbort(AbortLocation::Script, code) => {
+            Some(explain_abort(vm, metadata, None, *code))
+        }
+        _ => None,
+    }
+}
+
+/// Explains an aborted execution, or returns `None` if the error is not an abort. `metadata`
+/// is used as in `explain_vm_status`.
+pub fn explain_vm_error(
+    vm: &MoveVM,
+    metadata: &BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+    err: &VMError,
+) -> Option<AbortExplanation> {
+    match (err.major_status(), err.sub_status(), err.location()) {
+        (StatusCode::ABORTED, Some(code), Location::Module(module_id)) => {
+            Some(explain_abort(vm, metadata, Some(module_id), code))
+        }
+        (StatusCode::ABORTED, Some(code), _) => Some(explain_abort(vm, metadata, None, code)),
+        _ => None,
+    }
+}
+
+/// Looks the code up in the error map embedded in the Aptos metadata of the module, or in
+/// `metadata` if the module carries none. The module must have been loaded by the VM, which
+/// is the case for a module that aborted.
+fn explain_abort(
+    vm: &MoveVM,
+    metadata: &BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+    module_id: Option<&ModuleId>,
+    code: u64,
+) -> AbortExplanation {
+    let info = module_id
+        .and_then(|module_id| {
+            get_vm_metadata(vm, module_id.clone()).or_else(|| metadata.get(module_id).cloned())
+        })
+        .and_then(|metadata| metadata.extract_abort_info(code));
+    let (reason_name, description) = match info {
+        Some(info) => (Some(info.reason_name), Some(info.description)),
+        None => (None, None),
+    };
+    AbortExplanation {
+        module: module_id.map_or_else(|| "script".to_string(), ModuleId::to_string),
+        code,
+        category: error_category(code),
+        reason_name,
+        description,
+    }
+}
+
+/// Returns the name of the category of a code built with `0x1::error`, which keeps the
+/// category above the lower 16 bits of the reason.
+fn error_category(code: u64) -> Option<&'static str> {
+    Some(match code >> 16 {
+        0x1 => "INVALID_ARGUMENT",
+        0x2 => "OUT_OF_RANGE",
+        0x3 => "INVALID_STATE",
+        0x4 => "UNAUTHENTICATED",
+        0x5 => "PERMISSION_DENIED",
+        0x6 => "NOT_FOUND",
+        0x7 => "ABORTED",
+        0x8 => "ALREADY_EXISTS",
+        0x9 => "RESOURCE_EXHAUSTED",
+        0xA => "CANCELLED",
+        0xB => "INTERNAL",
+        0xC => "NOT_IMPLEMENTED",
+        0xD => "UNAVAILABLE",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framwork::natives::{aptos_natives, NativeGasParameters};
+    use move_core_types::account_address::AccountAddress;
+    use move_core_types::errmap::ErrorDescription;
+    use move_core_types::identifier::Identifier;
+
+    fn module_id() -> ModuleId {
+        ModuleId::new(AccountAddress::ONE, Identifier::new("coin").unwrap())
+    }
+
+    fn metadata() -> BTreeMap<ModuleId, RuntimeModuleMetadataV1> {
+        let error = |name: &str| ErrorDescription {
+            code_name: name.to_string(),
+            code_description: format!("{} happened", name),
+        };
+        let metadata = RuntimeModuleMetadataV1 {
+            error_map: BTreeMap::from([(0x1, error("ENO_COIN")), (0x1_0000, error("EBIG"))]),
+            ..RuntimeModuleMetadataV1::default()
+        };
+        BTreeMap::from([(module_id(), metadata)])
+    }
+
+    fn explain(module_id: Option<&ModuleId>, code: u64) -> AbortExplanation {
+        let vm = MoveVM::new(aptos_natives(NativeGasParameters::zeros())).unwrap();
+        explain_abort(&vm, &metadata(), module_id, code)
+    }
+
+    #[test]
+    fn categories_follow_the_error_module() {
+        assert_eq!(error_category(0x1_0001), Some("INVALID_ARGUMENT"));
+        assert_eq!(error_category(0x6_0002), Some("NOT_FOUND"));
+        assert_eq!(error_category(0xD_0000), Some("UNAVAILABLE"));
+        assert_eq!(error_category(0x1), None);
+        assert_eq!(error_category(0xE_0001), None);
+    }
+
+    #[test]
+    fn reasons_are_looked_up_by_the_lower_bits_of_the_code() {
+        let explanation = explain(Some(&module_id()), 0x6_0001);
+        assert_eq!(explanation.module, "0x1::coin");
+        assert_eq!(explanation.category, Some("NOT_FOUND"));
+        assert_eq!(explanation.reason_name.as_deref(), Some("ENO_COIN"));
+        assert_eq!(
+            explanation.description.as_deref(),
+            Some("ENO_COIN happened")
+        );
+
+        // Bits 12 to 15 are not part of the reason either
+        let explanation = explain(Some(&module_id()), 0x1001);
+        assert_eq!(explanation.category, None);
+        assert_eq!(explanation.reason_name.as_deref(), Some("ENO_COIN"));
+    }
+
+    #[test]
+    fn reasons_fall_back_to_the_full_code() {
+        let explanation = explain(Some(&module_id()), 0x1_0000);
+        assert_eq!(explanation.category, Some("INVALID_ARGUMENT"));
+        assert_eq!(explanation.reason_name.as_deref(), Some("EBIG"));
+    }
+
+    #[test]
+    fn unknown_codes_and_scripts_have_no_reason() {
+        let explanation = explain(Some(&module_id()), 0x2);
+        assert_eq!(explanation.reason_name, None);
+        assert_eq!(explanation.description, None);
+
+        let explanation = explain(None, 0x1);
+        assert_eq!(explanation.module, "script");
+        assert_eq!(explanation.reason_name, None);
+    }
+}
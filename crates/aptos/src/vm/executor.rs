@@ -0,0 +1,544 @@
+use super::{
+    explain_vm_status, move_value_to_json, AbortExplanation, GasProfile, GasSchedule,
+    InMemoryStorage, ProfilingGasMeter,
+};
+use crate::framwork::natives::code::NativeCodeContext;
+use crate::framwork::natives::event::NativeEventContext;
+use crate::framwork::natives::{aptos_natives, NativeGasParameters};
+use crate::framwork::{BuiltPackage, RuntimeModuleMetadataV1};
+use crate::toolchain::{ToolchainError, ToolchainResult};
+use move_binary_format::errors::{Location, VMResult};
+use move_core_types::account_address::AccountAddress;
+use move_core_types::effects::{ChangeSet, Event, Op};
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use move_core_types::value::MoveValue;
+use move_table_extension::{NativeTableContext, TableChangeSet, TableResolver};
+use move_vm_runtime::move_vm::MoveVM;
+use move_vm_runtime::native_extensions::NativeContextExtensions;
+use move_vm_runtime::session::{SerializedReturnValues, Session};
+use serde::Serialize;
+use serde_json::json;
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+
+/// The outcome of executing a function or script in a local VM session.
+#[derive(Debug, Serialize)]
+pub struct ExecutionOutput {
+    pub success: bool,
+    pub vm_status: String,
+    /// The explanation of the abort code, if the execution aborted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abort: Option<AbortExplanation>,
+    pub return_values: Vec<serde_json::Value>,
+    pub events: Vec<EventOutput>,
+    pub write_set: Vec<WriteSetChange>,
+    /// The gas consumed by the execution, if it was metered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_profile: Option<GasProfile>,
+}
+
+/// An event emitted during execution, with its payload decoded to JSON using the layout of
+/// its type.
+#[derive(Debug, Serialize)]
+pub struct EventOutput {
+    pub guid: String,
+    pub sequence_number: u64,
+    #[serde(rename = "type")]
+    pub type_tag: String,
+    pub data: serde_json::Value,
+}
+
+/// A single change to the storage produced by a successful execution.
+#[derive(Debug, Serialize)]
+pub struct WriteSetChange {
+    /// The account address, or the handle of the table for table items
+    pub address: String,
+    /// Either `module`, `resource` or `table_item`
+    pub kind: &'static str,
+    /// The module name, the resource type or the type of the table
+    pub name: String,
+    /// The key of a table item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<serde_json::Value>,
+    /// One of `new`, `modify` or `delete`
+    pub op: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Executes Move code of built packages in a `MoveVM` backed by an `InMemoryStorage`.
+///
+/// The changes of every successful execution are applied to the storage, so consecutive
+/// executions observe each other's effects. Executions are only metered if a gas schedule
+/// is set.
+pub struct LocalExecutor {
+    pub(super) vm: MoveVM,
+    pub(super) storage: InMemoryStorage,
+    /// The number of sessions started so far
+    sessions: u64,
+    gas_schedule: Option<GasSchedule>,
+    /// The runtime metadata of loaded modules whose bytecode carries none
+    pub(super) dependency_metadata: BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+}
+
+/// Creates the native context extensions required by the natives of `aptos_natives`.
+///
+/// The table natives derive the handles of new tables from `txn_hash`, so sessions whose
+/// changes are applied to the same storage must use distinct hashes.
+pub(crate) fn new_extensions<'a>(
+    resolver: &'a dyn TableResolver,
+    txn_hash: [u8; 32],
+) -> NativeContextExtensions<'a> {
+    let mut extensions = NativeContextExtensions::default();
+    extensions.add(NativeCodeContext::default());
+    extensions.add(NativeEventContext::default());
+    extensions.add(NativeTableContext::new(txn_hash, resolver));
+    extensions
+}
+
+/// Returns the hash of the `seq`-th session, which stands in for the hash of the transaction.
+pub(crate) fn session_hash(seq: u64) -> [u8; 32] {
+    Sha3_256::digest(&seq.to_le_bytes()).into()
+}
+
+/// Takes the events emitted during a finished session out of its extensions.
+pub(super) fn emitted_events(extensions: &mut NativeContextExtensions) -> Vec<Event> {
+    extensions.remove::<NativeEventContext>().into_events()
+}
+
+/// Takes the table changes of a finished session out of its extensions.
+pub(super) fn table_changes(extensions: &mut NativeContextExtensions) -> VMResult<TableChangeSet> {
+    extensions
+        .remove::<NativeTableContext>()
+        .into_change_set()
+        .map_err(|err| err.finish(Location::Undefined))
+}
+
+impl LocalExecutor {
+    pub fn new() -> ToolchainResult<Self> {
+        let vm = MoveVM::new(aptos_natives(NativeGasParameters::zeros())).map_err(|err| {
+            ToolchainError::UnexpectedError(format!(
+                "Failed to create Move VM: {}",
+                err.into_vm_status()
+            ))
+        })?;
+        Ok(Self {
+            vm,
+            storage: InMemoryStorage::new(),
+            sessions: 0,
+            gas_schedule: None,
+            dependency_metadata: BTreeMap::new(),
+        })
+    }
+
+    /// Meters the gas consumed by the following executions, which then report a gas profile.
+    /// An execution which runs out of gas fails and its changes are discarded.
+    pub fn set_gas_schedule(&mut self, gas_schedule: GasSchedule) {
+        self.gas_schedule = Some(gas_schedule);
+    }
+
+    pub fn storage(&self) -> &InMemoryStorage {
+        &self.storage
+    }
+
+    /// Returns a transaction hash for a new session, distinct from those of all previous
+    /// sessions of this executor.
+    pub(super) fn next_txn_hash(&mut self) -> [u8; 32] {
+        self.sessions += 1;
+        session_hash(self.sessions)
+    }
+
+    /// Adds the modules of the dependencies of the package to the storage, but not the
+    /// modules of the package itself, e.g. to publish it afterwards.
+    pub fn load_dependencies(&mut self, package: &BuiltPackage) {
+        for (module_id, blob) in package.extract_dependency_code() {
+            self.storage.add_module(module_id, blob);
+        }
+        self.add_dependency_metadata(package);
+    }
+
+    /// Adds the modules of the package and all of its dependencies to the storage.
+    pub fn load_package(&mut self, package: &BuiltPackage) {
+        for (module_id, blob) in package.extract_all_code() {
+            self.storage.add_module(module_id, blob);
+        }
+        self.add_dependency_metadata(package);
+    }
+
+    /// Keeps the error maps of the dependencies of the package, to explain aborts in them.
+    fn add_dependency_metadata(&mut self, package: &BuiltPackage) {
+        self.dependency_metadata.extend(
+            package
+                .dependency_metadata()
+                .iter()
+                .map(|(module_id, metadata)| (module_id.clone(), metadata.clone())),
+        );
+    }
+
+    /// Executes a function, regardless of its visibility, so that return values of
+    /// non-entry functions can be inspected as well.
+    pub fn execute_function(
+        &mut self,
+        module: &ModuleId,
+        function: &IdentStr,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+    ) -> ExecutionOutput {
+        self.execute(format!("{}::{}", module, function), |session, gas_meter| {
+            session.execute_function_bypass_visibility(module, function, ty_args, args, gas_meter)
+        })
+    }
+
+    /// Executes a compiled script.
+    pub fn execute_script(
+        &mut self,
+        script: Vec<u8>,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+    ) -> ExecutionOutput {
+        self.execute("script".to_string(), |session, gas_meter| {
+            session.execute_script(script, ty_args, args, gas_meter)
+        })
+    }
+
+    /// Runs `f` in a new session and applies its changes, charging gas for the execution and
+    /// the changes. `entry` is the name of the executed function, which the gas profile
+    /// attributes the cost of its instructions to.
+    fn execute(
+        &mut self,
+        entry: String,
+        f: impl FnOnce(
+            &mut Session<'_, '_, InMemoryStorage>,
+            &mut ProfilingGasMeter,
+        ) -> VMResult<SerializedReturnValues>,
+    ) -> ExecutionOutput {
+        let txn_hash = self.next_txn_hash();
+        let mut gas_meter = ProfilingGasMeter::new(
+            self.gas_schedule.clone().unwrap_or_else(GasSchedule::free),
+            entry,
+        );
+        let result = {
+            let mut session = self.vm.new_session_with_extensions(
+                &self.storage,
+                new_extensions(&self.storage, txn_hash),
+            );
+            f(&mut session, &mut gas_meter).and_then(|values| {
+                // Events are collected by `NativeEventContext` rather than the session
+                let (changes, _, mut extensions) = session.finish_with_extensions()?;
+                let table_changes = table_changes(&mut extensions)?;
+                let events = emitted_events(&mut extensions);
+                let mut changes_summary = summarize_changes(&changes);
+                changes_summary.extend(summarize_table_changes(&table_changes, &self.storage));
+                charge_storage_writes(&mut gas_meter, &changes_summary)?;
+                Ok((values, changes, table_changes, changes_summary, events))
+            })
+        };
+        let gas_profile = self
+            .gas_schedule
+            .is_some()
+            .then(|| gas_meter.into_profile());
+        match result {
+            Ok((values, changes, table_changes, changes_summary, events)) => {
+                let return_values = values
+                    .return_values
+                    .into_iter()
+                    .map(|(blob, layout)| {
+                        MoveValue::simple_deserialize(&blob, &layout)
+                            .map(move_value_to_json)
+                            .unwrap_or_else(|_| hex_json(&blob))
+                    })
+                    .collect();
+                self.storage.apply(changes);
+                self.storage.apply_table_changes(table_changes);
+                ExecutionOutput {
+                    success: true,
+                    vm_status: "Executed successfully".to_string(),
+                    abort: None,
+                    return_values,
+                    events: self.decode_events(events),
+                    write_set: self.decode_changes(changes_summary),
+                    gas_profile,
+                }
+            }
+            Err(err) => {
+                let vm_status = err.into_vm_status();
+                ExecutionOutput {
+                    success: false,
+                    abort: explain_vm_status(&self.vm, &self.dependency_metadata, &vm_status),
+                    vm_status: vm_status.to_string(),
+                    return_values: vec![],
+                    events: vec![],
+                    write_set: vec![],
+                    gas_profile,
+                }
+            }
+        }
+    }
+
+    /// Decodes a BCS blob of the given type into JSON, using the type layouts of the modules
+    /// currently in storage. Falls back to the hex encoded blob if the type is unknown.
+    pub fn decode_value(&self, type_tag: &TypeTag, blob: &[u8]) -> serde_json::Value {
+        let session = self.vm.new_session(&self.storage);
+        session
+            .get_fully_annotated_type_layout(type_tag)
+            .ok()
+            .and_then(|layout| MoveValue::simple_deserialize(blob, &layout).ok())
+            .map(move_value_to_json)
+            .unwrap_or_else(|| hex_json(blob))
+    }
+
+    pub(super) fn decode_events(&self, events: Vec<Event>) -> Vec<EventOutput> {
+        events
+            .into_iter()
+            .map(|(guid, sequence_number, type_tag, blob)| EventOutput {
+                guid: format!("0x{}", hex::encode(guid)),
+                sequence_number,
+                data: self.decode_value(&type_tag, &blob),
+                type_tag: type_tag.to_string(),
+            })
+            .collect()
+    }
+
+    pub(super) fn decode_changes(&self, changes: Vec<ChangeSummary>) -> Vec<WriteSetChange> {
+        changes
+            .into_iter()
+            .map(|change| {
+                let data = match (&change.type_tag, &change.blob) {
+                    (Some(type_tag), Some(blob)) => Some(self.decode_value(type_tag, blob)),
+                    (None, Some(blob)) => Some(hex_json(blob)),
+                    (_, None) => None,
+                };
+                let key = change.key.map(|(key_type, key)| match key_type {
+                    Some(key_type) => self.decode_value(&key_type, &key),
+                    None => hex_json(&key),
+                });
+                WriteSetChange {
+                    address: change.address.to_hex_literal(),
+                    kind: change.kind,
+                    name: change.name,
+                    key,
+                    op: change.op,
+                    data,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A change set entry detached from the change set, so it can be decoded after the change
+/// set has been applied to the storage.
+pub(super) struct ChangeSummary {
+    address: AccountAddress,
+    kind: &'static str,
+    name: String,
+    /// The key of a table item, with its type if the table is known
+    key: Option<(Option<TypeTag>, Vec<u8>)>,
+    /// The resource type or the value type of a table item, `None` for modules
+    type_tag: Option<TypeTag>,
+    op: &'static str,
+    blob: Option<Vec<u8>>,
+}
+
+pub(super) fn summarize_changes(changes: &ChangeSet) -> Vec<ChangeSummary> {
+    let mut summary = vec![];
+    for (address, account_changes) in changes.accounts() {
+        for (name, op) in account_changes.modules() {
+            let (op, blob) = summarize_op(op);
+            summary.push(ChangeSummary {
+                address: *address,
+                kind: "module",
+                name: name.to_string(),
+                key: None,
+                type_tag: None,
+                op,
+                blob,
+            });
+        }
+        for (tag, op) in account_changes.resources() {
+            let (op, blob) = summarize_op(op);
+            summary.push(ChangeSummary {
+                address: *address,
+                kind: "resource",
+                name: tag.to_string(),
+                key: None,
+                type_tag: Some(TypeTag::Struct(Box::new(tag.clone()))),
+                op,
+                blob,
+            });
+        }
+    }
+    summary
+}
+
+/// Summarizes the changed table items, looking up the types of tables which already existed
+/// before the session in `storage`.
+pub(super) fn summarize_table_changes(
+    changes: &TableChangeSet,
+    storage: &InMemoryStorage,
+) -> Vec<ChangeSummary> {
+    let mut summary = vec![];
+    for (handle, change) in &changes.changes {
+        let info = changes
+            .new_tables
+            .get(handle)
+            .or_else(|| storage.table_info(handle));
+        for (key, op) in &change.entries {
+            let (op, blob) = summarize_op(op);
+            summary.push(ChangeSummary {
+                address: handle.0,
+                kind: "table_item",
+                name: info.map_or_else(
+                    || "table".to_string(),
+                    |info| format!("table<{}, {}>", info.key_type, info.value_type),
+                ),
+                key: Some((info.map(|info| info.key_type.clone()), key.clone())),
+                type_tag: info.map(|info| info.value_type.clone()),
+                op,
+                blob,
+            });
+        }
+    }
+    summary
+}
+
+/// Charges for every change of a finished session, by the size of the written blob.
+fn charge_storage_writes(
+    gas_meter: &mut ProfilingGasMeter,
+    changes: &[ChangeSummary],
+) -> VMResult<()> {
+    for change in changes {
+        gas_meter
+            .charge_storage_write(
+                change.address.to_hex_literal(),
+                change.kind,
+                change.name.clone(),
+                change.op,
+                change.blob.as_ref().map_or(0, |blob| blob.len() as u64),
+            )
+            .map_err(|err| err.finish(Location::Undefined))?;
+    }
+    Ok(())
+}
+
+fn summarize_op(op: &Op<Vec<u8>>) -> (&'static str, Option<Vec<u8>>) {
+    match op {
+        Op::New(blob) => ("new", Some(blob.clone())),
+        Op::Modify(blob) => ("modify", Some(blob.clone())),
+        Op::Delete => ("delete", None),
+    }
+}
+
+fn hex_json(blob: &[u8]) -> serde_json::Value {
+    json!(format!("0x{}", hex::encode(blob)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::StorageGasSchedule;
+    use move_compiler::shared::NumericalAddress;
+    use move_compiler::Compiler;
+    use move_core_types::identifier::Identifier;
+    use move_core_types::language_storage::StructTag;
+    use std::collections::BTreeMap;
+
+    const STORE_MODULE: &str = r#"
+        module 0xcafe::store {
+            struct Item has key { value: u64 }
+
+            public entry fun put(account: &signer) {
+                move_to(account, Item { value: 42 })
+            }
+        }
+    "#;
+
+    fn executor_with_store_module() -> (LocalExecutor, ModuleId) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.move");
+        std::fs::write(&path, STORE_MODULE).unwrap();
+        let (_, units) = Compiler::from_files(
+            vec![path.display().to_string()],
+            vec![],
+            BTreeMap::<String, NumericalAddress>::new(),
+        )
+        .build_and_report()
+        .unwrap();
+        let mut executor = LocalExecutor::new().unwrap();
+        let module_id = ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("store").unwrap(),
+        );
+        for unit in units {
+            executor
+                .storage
+                .add_module(module_id.clone(), unit.into_compiled_unit().serialize(None));
+        }
+        (executor, module_id)
+    }
+
+    fn put(executor: &mut LocalExecutor, module_id: &ModuleId) -> ExecutionOutput {
+        let signer = MoveValue::Signer(AccountAddress::ONE)
+            .simple_serialize()
+            .unwrap();
+        executor.execute_function(
+            module_id,
+            IdentStr::new("put").unwrap(),
+            vec![],
+            vec![signer],
+        )
+    }
+
+    fn item(executor: &LocalExecutor, module_id: &ModuleId) -> Option<Vec<u8>> {
+        let tag = StructTag {
+            address: *module_id.address(),
+            module: module_id.name().to_owned(),
+            name: Identifier::new("Item").unwrap(),
+            type_params: vec![],
+        };
+        executor
+            .storage()
+            .resource(&AccountAddress::ONE, &tag)
+            .map(<[u8]>::to_vec)
+    }
+
+    fn schedule(max_gas: u64) -> GasSchedule {
+        GasSchedule {
+            storage: StorageGasSchedule {
+                per_item_write: 1_000,
+                ..StorageGasSchedule::default()
+            },
+            ..GasSchedule::unit(max_gas)
+        }
+    }
+
+    #[test]
+    fn storage_writes_are_applied_when_gas_suffices() {
+        let (mut executor, module_id) = executor_with_store_module();
+        executor.set_gas_schedule(schedule(10_000));
+        let output = put(&mut executor, &module_id);
+        assert!(output.success, "{}", output.vm_status);
+        assert_eq!(output.write_set.len(), 1);
+        assert!(item(&executor, &module_id).is_some());
+    }
+
+    #[test]
+    fn running_out_of_gas_while_charging_storage_discards_the_write_set() {
+        let (mut executor, module_id) = executor_with_store_module();
+        // Enough for the instructions, but not for writing the resource
+        executor.set_gas_schedule(schedule(500));
+        let output = put(&mut executor, &module_id);
+        assert!(!output.success);
+        assert!(
+            output.vm_status.contains("OUT_OF_GAS"),
+            "{}",
+            output.vm_status
+        );
+        assert!(output.write_set.is_empty());
+        assert!(item(&executor, &module_id).is_none());
+        let profile = output.gas_profile.unwrap();
+        assert_eq!(profile.total, 500);
+        assert_eq!(profile.storage_writes.len(), 1);
+        assert!(profile.storage > 0);
+    }
+}
@@ -0,0 +1,558 @@
+use crate::toolchain::{ToolchainError, ToolchainResult};
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::gas_algebra::{InternalGas, NumArgs, NumBytes};
+use move_core_types::language_storage::ModuleId;
+use move_core_types::vm_status::StatusCode;
+use move_vm_types::gas::{GasMeter, SimpleInstruction};
+use move_vm_types::views::{TypeView, ValueView};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The costs charged by `ProfilingGasMeter`, in internal gas units.
+///
+/// Fields which are missing in the JSON file keep their default, which charges one unit per
+/// instruction and nothing else.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasSchedule {
+    /// The gas available to a single execution
+    pub max_gas: u64,
+    /// The cost of an instruction which is not listed in `instructions`
+    pub default_instruction: u64,
+    /// The cost of each kind of instruction, keyed by the name of the bytecode, e.g. `Add`,
+    /// `CallGeneric` or `MutBorrowGlobal`
+    pub instructions: BTreeMap<String, u64>,
+    /// The cost per abstract memory unit of the values an instruction copies, moves, packs or
+    /// compares
+    pub per_abstract_value_unit: u64,
+    /// The cost of calling a native function, on top of the cost reported by the native
+    pub native_call: u64,
+    pub storage: StorageGasSchedule,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageGasSchedule {
+    pub per_item_read: u64,
+    pub per_byte_read: u64,
+    /// The cost of creating or modifying a module, resource or table item
+    pub per_item_write: u64,
+    pub per_byte_write: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self::unit(1_000_000)
+    }
+}
+
+impl GasSchedule {
+    /// Charges one unit per instruction, so `max_gas` bounds the number of instructions.
+    pub fn unit(max_gas: u64) -> Self {
+        Self {
+            max_gas,
+            default_instruction: 1,
+            instructions: BTreeMap::new(),
+            per_abstract_value_unit: 0,
+            native_call: 0,
+            storage: StorageGasSchedule::default(),
+        }
+    }
+
+    /// Charges nothing, for executions which are not metered.
+    pub fn free() -> Self {
+        Self {
+            default_instruction: 0,
+            ..Self::unit(u64::MAX)
+        }
+    }
+
+    pub fn from_file(path: &Path) -> ToolchainResult<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| ToolchainError::IO(path.display().to_string(), err))?;
+        serde_json::from_str(&contents)
+            .map_err(|err| ToolchainError::UnableToParse("gas schedule", err.to_string()))
+    }
+
+    fn instruction(&self, name: &str) -> u64 {
+        self.instructions
+            .get(name)
+            .copied()
+            .unwrap_or(self.default_instruction)
+    }
+}
+
+/// The gas consumed by an execution, broken down by what it was spent on.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GasProfile {
+    pub max_gas: u64,
+    /// The sum of `execution` and `storage`
+    pub total: u64,
+    pub execution: u64,
+    pub storage: u64,
+    /// The gas consumed by the instructions of each function, excluding its callees
+    pub by_function: BTreeMap<String, u64>,
+    /// The gas consumed by each kind of instruction
+    pub by_instruction: BTreeMap<String, u64>,
+    /// The gas consumed by each native function, including the cost of the call
+    pub by_native: BTreeMap<String, u64>,
+    pub storage_writes: Vec<StorageWriteGas>,
+}
+
+/// The gas charged for a single change to the storage.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageWriteGas {
+    pub address: String,
+    /// Either `module`, `resource` or `table_item`
+    pub kind: &'static str,
+    pub name: String,
+    /// One of `new`, `modify` or `delete`
+    pub op: &'static str,
+    pub bytes: u64,
+    pub gas: u64,
+}
+
+/// A gas meter which charges according to a `GasSchedule` and records a `GasProfile`.
+///
+/// Costs are attributed to the function on top of a call stack which is tracked from the
+/// calls and returns the VM charges for. Natives are charged by the VM right after their
+/// call, so they are popped off the stack once their cost is known.
+pub struct ProfilingGasMeter {
+    schedule: GasSchedule,
+    balance: u64,
+    call_stack: Vec<String>,
+    profile: GasProfile,
+}
+
+impl ProfilingGasMeter {
+    /// Creates a meter for an execution which starts in `entry`, e.g. `0x1::coin::transfer`.
+    pub fn new(schedule: GasSchedule, entry: String) -> Self {
+        Self {
+            balance: schedule.max_gas,
+            profile: GasProfile {
+                max_gas: schedule.max_gas,
+                ..GasProfile::default()
+            },
+            schedule,
+            call_stack: vec![entry],
+        }
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.profile.total
+    }
+
+    pub fn into_profile(self) -> GasProfile {
+        self.profile
+    }
+
+    /// Charges for a change to the storage made by the finished session. Deleting is free.
+    pub fn charge_storage_write(
+        &mut self,
+        address: String,
+        kind: &'static str,
+        name: String,
+        op: &'static str,
+        bytes: u64,
+    ) -> PartialVMResult<()> {
+        let cost = if op == "delete" {
+            0
+        } else {
+            let storage = &self.schedule.storage;
+            storage
+                .per_byte_write
+                .saturating_mul(bytes)
+                .saturating_add(storage.per_item_write)
+        };
+        let before = self.balance;
+        let result = self.withdraw(cost);
+        let gas = before - self.balance;
+        self.profile.storage += gas;
+        self.profile.storage_writes.push(StorageWriteGas {
+            address,
+            kind,
+            name,
+            op,
+            bytes,
+            gas,
+        });
+        result
+    }
+
+    /// Takes `cost` from the balance. If the balance is too low, it is drained and the
+    /// execution runs out of gas.
+    fn withdraw(&mut self, cost: u64) -> PartialVMResult<()> {
+        if cost > self.balance {
+            self.profile.total += self.balance;
+            self.balance = 0;
+            return Err(PartialVMError::new(StatusCode::OUT_OF_GAS));
+        }
+        self.balance -= cost;
+        self.profile.total += cost;
+        Ok(())
+    }
+
+    /// Charges an instruction to the function on top of the call stack.
+    fn charge_instr(&mut self, name: &str, size: u64) -> PartialVMResult<()> {
+        let cost = self
+            .schedule
+            .per_abstract_value_unit
+            .saturating_mul(size)
+            .saturating_add(self.schedule.instruction(name));
+        self.charge_execution(name, cost)
+    }
+
+    fn charge_execution(&mut self, name: &str, cost: u64) -> PartialVMResult<()> {
+        let before = self.balance;
+        let result = self.withdraw(cost);
+        let gas = before - self.balance;
+        self.profile.execution += gas;
+        *self
+            .profile
+            .by_instruction
+            .entry(name.to_string())
+            .or_default() += gas;
+        if let Some(function) = self.call_stack.last() {
+            *self
+                .profile
+                .by_function
+                .entry(function.clone())
+                .or_default() += gas;
+        }
+        result
+    }
+
+    fn charge_native(&mut self, cost: u64) -> PartialVMResult<()> {
+        let before = self.balance;
+        let result = self.charge_execution("CallNative", cost);
+        let gas = before - self.balance;
+        if let Some(native) = self.call_stack.last() {
+            *self.profile.by_native.entry(native.clone()).or_default() += gas;
+        }
+        result
+    }
+}
+
+fn size(val: impl ValueView) -> u64 {
+    u64::from(val.legacy_abstract_memory_size())
+}
+
+fn sizes(vals: impl ExactSizeIterator<Item = impl ValueView>) -> u64 {
+    vals.map(size).sum()
+}
+
+impl GasMeter for ProfilingGasMeter {
+    fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
+        let result = self.charge_instr(&format!("{:?}", instr), 0);
+        if let SimpleInstruction::Ret = instr {
+            self.call_stack.pop();
+        }
+        result
+    }
+
+    fn charge_call(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let result = self.charge_instr("Call", sizes(args));
+        self.call_stack
+            .push(format!("{}::{}", module_id, func_name));
+        result
+    }
+
+    fn charge_call_generic(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        _ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let result = self.charge_instr("CallGeneric", sizes(args));
+        self.call_stack
+            .push(format!("{}::{}", module_id, func_name));
+        result
+    }
+
+    fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()> {
+        self.charge_instr("LdConst", u64::from(size))
+    }
+
+    fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("CopyLoc", size(val))
+    }
+
+    fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("MoveLoc", size(val))
+    }
+
+    fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("StLoc", size(val))
+    }
+
+    fn charge_pack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let name = if is_generic { "PackGeneric" } else { "Pack" };
+        self.charge_instr(name, sizes(args))
+    }
+
+    fn charge_unpack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let name = if is_generic {
+            "UnpackGeneric"
+        } else {
+            "Unpack"
+        };
+        self.charge_instr(name, sizes(args))
+    }
+
+    fn charge_read_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("ReadRef", size(val))
+    }
+
+    fn charge_write_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("WriteRef", size(val))
+    }
+
+    fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("Eq", size(lhs) + size(rhs))
+    }
+
+    fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_instr("Neq", size(lhs) + size(rhs))
+    }
+
+    fn charge_borrow_global(
+        &mut self,
+        is_mut: bool,
+        is_generic: bool,
+        _ty: impl TypeView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let name = match (is_mut, is_generic) {
+            (false, false) => "ImmBorrowGlobal",
+            (false, true) => "ImmBorrowGlobalGeneric",
+            (true, false) => "MutBorrowGlobal",
+            (true, true) => "MutBorrowGlobalGeneric",
+        };
+        self.charge_instr(name, 0)
+    }
+
+    fn charge_exists(
+        &mut self,
+        is_generic: bool,
+        _ty: impl TypeView,
+        _exists: bool,
+    ) -> PartialVMResult<()> {
+        let name = if is_generic {
+            "ExistsGeneric"
+        } else {
+            "Exists"
+        };
+        self.charge_instr(name, 0)
+    }
+
+    fn charge_move_from(
+        &mut self,
+        is_generic: bool,
+        _ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let name = if is_generic {
+            "MoveFromGeneric"
+        } else {
+            "MoveFrom"
+        };
+        self.charge_instr(name, val.map_or(0, size))
+    }
+
+    fn charge_move_to(
+        &mut self,
+        is_generic: bool,
+        _ty: impl TypeView,
+        val: impl ValueView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let name = if is_generic {
+            "MoveToGeneric"
+        } else {
+            "MoveTo"
+        };
+        self.charge_instr(name, size(val))
+    }
+
+    fn charge_vec_pack<'a>(
+        &mut self,
+        _ty: impl TypeView + 'a,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_instr("VecPack", sizes(args))
+    }
+
+    fn charge_vec_len(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge_instr("VecLen", 0)
+    }
+
+    fn charge_vec_borrow(
+        &mut self,
+        is_mut: bool,
+        _ty: impl TypeView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let name = if is_mut {
+            "VecMutBorrow"
+        } else {
+            "VecImmBorrow"
+        };
+        self.charge_instr(name, 0)
+    }
+
+    fn charge_vec_push_back(
+        &mut self,
+        _ty: impl TypeView,
+        val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        self.charge_instr("VecPushBack", size(val))
+    }
+
+    fn charge_vec_pop_back(
+        &mut self,
+        _ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_instr("VecPopBack", val.map_or(0, size))
+    }
+
+    fn charge_vec_unpack(
+        &mut self,
+        _ty: impl TypeView,
+        _expect_num_elements: NumArgs,
+        elems: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_instr("VecUnpack", sizes(elems))
+    }
+
+    fn charge_vec_swap(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        self.charge_instr("VecSwap", 0)
+    }
+
+    fn charge_load_resource(
+        &mut self,
+        loaded: Option<(NumBytes, impl ValueView)>,
+    ) -> PartialVMResult<()> {
+        let storage = &self.schedule.storage;
+        let cost = match loaded {
+            Some((bytes, _)) => storage
+                .per_byte_read
+                .saturating_mul(u64::from(bytes))
+                .saturating_add(storage.per_item_read),
+            None => storage.per_item_read,
+        };
+        self.charge_execution("LoadResource", cost)
+    }
+
+    fn charge_native_function(
+        &mut self,
+        amount: InternalGas,
+        _ret_vals: Option<impl ExactSizeIterator<Item = impl ValueView>>,
+    ) -> PartialVMResult<()> {
+        let cost = u64::from(amount).saturating_add(self.schedule.native_call);
+        let result = self.charge_native(cost);
+        self.call_stack.pop();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::account_address::AccountAddress;
+    use move_core_types::identifier::Identifier;
+    use move_vm_types::values::Value;
+    use std::iter;
+
+    fn meter(max_gas: u64) -> ProfilingGasMeter {
+        let schedule = GasSchedule {
+            per_abstract_value_unit: 2,
+            native_call: 5,
+            storage: StorageGasSchedule {
+                per_item_write: 100,
+                per_byte_write: 1,
+                ..StorageGasSchedule::default()
+            },
+            ..GasSchedule::unit(max_gas)
+        };
+        ProfilingGasMeter::new(schedule, "0xcafe::m::main".to_string())
+    }
+
+    /// Runs `main`, which calls `callee` and the native `hash`, charging until the meter
+    /// runs out of gas.
+    fn run(meter: &mut ProfilingGasMeter) -> PartialVMResult<()> {
+        let module = ModuleId::new(
+            AccountAddress::from_hex_literal("0xcafe").unwrap(),
+            Identifier::new("m").unwrap(),
+        );
+        meter.charge_simple_instr(SimpleInstruction::LdU64)?;
+        meter.charge_call(&module, "callee", vec![Value::u64(1)].into_iter())?;
+        meter.charge_copy_loc(Value::u64(1))?;
+        meter.charge_simple_instr(SimpleInstruction::Ret)?;
+        meter.charge_call(&module, "hash", iter::empty::<Value>())?;
+        meter.charge_native_function(InternalGas::new(7), None::<iter::Empty<Value>>)?;
+        meter.charge_simple_instr(SimpleInstruction::Ret)?;
+        meter.charge_storage_write(
+            "0xcafe".to_string(),
+            "resource",
+            "0xcafe::m::R".to_string(),
+            "new",
+            8,
+        )
+    }
+
+    fn assert_totals(profile: &GasProfile) {
+        assert_eq!(profile.by_function.values().sum::<u64>(), profile.execution);
+        assert_eq!(
+            profile.by_instruction.values().sum::<u64>(),
+            profile.execution
+        );
+        assert_eq!(profile.total, profile.execution + profile.storage);
+        assert_eq!(
+            profile
+                .storage_writes
+                .iter()
+                .map(|write| write.gas)
+                .sum::<u64>(),
+            profile.storage
+        );
+    }
+
+    #[test]
+    fn profile_totals_add_up() {
+        let mut meter = meter(1_000);
+        run(&mut meter).unwrap();
+        let profile = meter.into_profile();
+        assert_totals(&profile);
+        assert_eq!(profile.by_function.len(), 3);
+        assert_eq!(profile.by_native.values().sum::<u64>(), 12);
+        assert_eq!(profile.storage, 108);
+    }
+
+    #[test]
+    fn profile_totals_add_up_when_out_of_gas() {
+        for max_gas in [0, 3, 10, 30] {
+            let mut meter = meter(max_gas);
+            let err = run(&mut meter).unwrap_err();
+            assert_eq!(err.major_status(), StatusCode::OUT_OF_GAS);
+            let profile = meter.into_profile();
+            assert_totals(&profile);
+            assert_eq!(profile.total, max_gas);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+mod abort;
+mod executor;
+mod gas;
+mod publish;
+mod storage;
+mod unit_test;
+mod value;
+
+pub use abort::{explain_vm_error, explain_vm_status, AbortExplanation};
+pub use executor::{EventOutput, ExecutionOutput, LocalExecutor, WriteSetChange};
+pub use gas::{GasProfile, GasSchedule, ProfilingGasMeter, StorageGasSchedule, StorageWriteGas};
+pub use publish::PublishOutput;
+pub use storage::InMemoryStorage;
+pub use unit_test::{run_unit_tests, TestResult, TestStatus, TestSummary};
+pub use value::move_value_to_json;
@@ -0,0 +1,311 @@
+use super::executor::{
+    emitted_events, new_extensions, summarize_changes, summarize_table_changes, table_changes,
+};
+use super::{explain_vm_status, AbortExplanation, EventOutput, LocalExecutor, WriteSetChange};
+use crate::framwork::natives::code::{
+    NativeCodeContext, PackageMetadata, PackageRegistry, PublishRequest, UpgradePolicy,
+};
+use crate::framwork::{check_upgrade, PackageMetadataView, StoredPackage};
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::compatibility::Compatibility;
+use move_binary_format::errors::VMError;
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::ident_str;
+use move_core_types::language_storage::{ModuleId, StructTag, CORE_CODE_ADDRESS};
+use move_core_types::value::MoveValue;
+use move_vm_types::gas::UnmeteredGasMeter;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// The outcome of publishing a package in a local VM.
+#[derive(Debug, Serialize)]
+pub struct PublishOutput {
+    pub success: bool,
+    pub vm_status: String,
+    /// The explanation of the abort code, if the execution aborted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abort: Option<AbortExplanation>,
+    /// The reasons why the publish request was rejected, if it was
+    pub errors: Vec<String>,
+    pub destination: String,
+    /// The ids of the published modules
+    pub published_modules: Vec<String>,
+    /// The ids of the new modules whose `init_module` function was run
+    pub initialized_modules: Vec<String>,
+    pub events: Vec<EventOutput>,
+    pub write_set: Vec<WriteSetChange>,
+    /// The packages published under the destination account after publishing
+    pub registry: Vec<PackageMetadataView>,
+}
+
+enum PublishFailure {
+    Vm(VMError),
+    Rejected(Vec<String>),
+}
+
+impl From<VMError> for PublishFailure {
+    fn from(err: VMError) -> Self {
+        PublishFailure::Vm(err)
+    }
+}
+
+impl LocalExecutor {
+    /// Publishes a package the way a `code::publish_package_txn` transaction does.
+    ///
+    /// The `code` module of the Aptos framework must be in storage. After the call, the
+    /// module bundle requested through `NativeCodeContext` is validated: the modules must be
+    /// the expected ones and located at the destination, their dependencies must be allowed,
+    /// and upgrades must be compatible unless the policy is `arbitrary`. The bundle is then
+    /// published and `init_module` is run for every new module which declares it. The
+    /// storage is only changed if all steps succeed.
+    pub fn publish_package(
+        &mut self,
+        sender: AccountAddress,
+        metadata: &PackageMetadata,
+        code: Vec<Vec<u8>>,
+    ) -> PublishOutput {
+        let mut output = PublishOutput {
+            success: false,
+            vm_status: String::new(),
+            abort: None,
+            errors: vec![],
+            destination: sender.to_hex_literal(),
+            published_modules: vec![],
+            initialized_modules: vec![],
+            events: vec![],
+            write_set: vec![],
+            registry: vec![],
+        };
+        match self.try_publish(sender, metadata, code, &mut output) {
+            Ok(()) => {
+                output.success = true;
+                output.vm_status = "Executed successfully".to_string();
+                output.registry = self.package_registry(sender);
+            }
+            Err(PublishFailure::Vm(err)) => {
+                let vm_status = err.into_vm_status();
+                output.abort = explain_vm_status(&self.vm, &self.dependency_metadata, &vm_status);
+                output.vm_status = vm_status.to_string();
+            }
+            Err(PublishFailure::Rejected(errors)) => {
+                output.vm_status = "Publish request rejected".to_string();
+                output.errors = errors;
+            }
+        }
+        output
+    }
+
+    fn try_publish(
+        &mut self,
+        sender: AccountAddress,
+        metadata: &PackageMetadata,
+        code: Vec<Vec<u8>>,
+        output: &mut PublishOutput,
+    ) -> Result<(), PublishFailure> {
+        let metadata_blob = bcs::to_bytes(metadata).expect("BCS for PackageMetadata");
+        let args = vec![
+            signer_arg(sender),
+            bcs::to_bytes(&metadata_blob).expect("BCS for vector<u8>"),
+            bcs::to_bytes(&code).expect("BCS for vector<vector<u8>>"),
+        ];
+        let txn_hash = self.next_txn_hash();
+        let mut session = self
+            .vm
+            .new_session_with_extensions(&self.storage, new_extensions(&self.storage, txn_hash));
+        session.execute_function_bypass_visibility(
+            &ModuleId::new(CORE_CODE_ADDRESS, ident_str!("code").to_owned()),
+            ident_str!("publish_package_txn"),
+            vec![],
+            args,
+            &mut UnmeteredGasMeter,
+        )?;
+        let (changes, _, mut extensions) = session.finish_with_extensions()?;
+        let table_changes_of_call = table_changes(&mut extensions)?;
+        let mut events = emitted_events(&mut extensions);
+        let request = extensions
+            .remove::<NativeCodeContext>()
+            .requested_module_bundle
+            .ok_or_else(|| {
+                PublishFailure::Rejected(vec![
+                    "`code::publish_package_txn` did not request to publish a bundle".to_string(),
+                ])
+            })?;
+        let modules = self.validate_publish_request(&metadata.name, &request)?;
+
+        // Publish into a copy of the storage, so nothing is changed if a step fails
+        let mut storage = self.storage.clone();
+        let mut summary = summarize_changes(&changes);
+        summary.extend(summarize_table_changes(&table_changes_of_call, &storage));
+        storage.apply(changes);
+        storage.apply_table_changes(table_changes_of_call);
+        let txn_hash = self.next_txn_hash();
+        let (publish_changes, publish_table_changes, publish_events) = {
+            let mut session = self
+                .vm
+                .new_session_with_extensions(&storage, new_extensions(&storage, txn_hash));
+            session.publish_module_bundle_with_compat_config(
+                request.bundle.into_inner(),
+                request.destination,
+                &mut UnmeteredGasMeter,
+                // Compatibility was checked while validating the request
+                Compatibility::no_check(),
+            )?;
+            for module in &modules {
+                let id = module.self_id();
+                if !self.storage.has_module(&id) && has_init_module(module) {
+                    session.execute_function_bypass_visibility(
+                        &id,
+                        ident_str!("init_module"),
+                        vec![],
+                        vec![signer_arg(request.destination)],
+                        &mut UnmeteredGasMeter,
+                    )?;
+                    output.initialized_modules.push(id.to_string());
+                }
+            }
+            let (changes, _, mut extensions) = session.finish_with_extensions()?;
+            (
+                changes,
+                table_changes(&mut extensions)?,
+                emitted_events(&mut extensions),
+            )
+        };
+        summary.extend(summarize_changes(&publish_changes));
+        summary.extend(summarize_table_changes(&publish_table_changes, &storage));
+        storage.apply(publish_changes);
+        storage.apply_table_changes(publish_table_changes);
+        events.extend(publish_events);
+        self.storage = storage;
+
+        output.published_modules = modules
+            .iter()
+            .map(|module| module.self_id().to_string())
+            .collect();
+        output.events = self.decode_events(events);
+        output.write_set = self.decode_changes(summary);
+        Ok(())
+    }
+
+    /// Checks a publish request the way the Aptos VM does before publishing the bundle.
+    fn validate_publish_request(
+        &self,
+        package: &str,
+        request: &PublishRequest,
+    ) -> Result<Vec<CompiledModule>, PublishFailure> {
+        let mut errors = vec![];
+        let mut modules = vec![];
+        for blob in request.bundle.iter() {
+            match CompiledModule::deserialize(blob.code()) {
+                Ok(module) => modules.push(module),
+                Err(err) => errors.push(format!("a module cannot be deserialized: {}", err)),
+            }
+        }
+
+        let mut names = BTreeSet::new();
+        for module in &modules {
+            let id = module.self_id();
+            if *id.address() != request.destination {
+                errors.push(format!(
+                    "module `{}` is not located at the destination {}",
+                    id,
+                    request.destination.to_hex_literal()
+                ));
+            }
+            names.insert(id.name().to_string());
+            if let Some(allowed_deps) = &request.allowed_deps {
+                for dep in module.immediate_dependencies() {
+                    let allowed = allowed_deps.get(dep.address()).map_or(false, |names| {
+                        names.contains("") || names.contains(dep.name().as_str())
+                    });
+                    if !allowed {
+                        errors.push(format!(
+                            "module `{}` depends on `{}`, which is not an allowed dependency",
+                            id, dep
+                        ));
+                    }
+                }
+            }
+        }
+        for name in request.expected_modules.difference(&names) {
+            errors.push(format!(
+                "module `{}` is listed in the package metadata, but not part of the bundle",
+                name
+            ));
+        }
+        for name in names.difference(&request.expected_modules) {
+            errors.push(format!(
+                "module `{}` is part of the bundle, but not listed in the package metadata",
+                name
+            ));
+        }
+
+        if request.check_compat {
+            let old_modules = modules
+                .iter()
+                .filter_map(|module| self.storage.module(&module.self_id()))
+                .filter_map(|blob| CompiledModule::deserialize(blob).ok())
+                .collect::<Vec<_>>();
+            let report = check_upgrade(
+                package,
+                UpgradePolicy::compat(),
+                &old_modules,
+                UpgradePolicy::compat(),
+                &modules,
+                false,
+            );
+            errors.extend(
+                report
+                    .violations
+                    .into_iter()
+                    .map(|violation| match violation.item {
+                        Some(item) => {
+                            format!("{}::{}: {}", violation.module, item, violation.message)
+                        }
+                        None => format!("{}: {}", violation.module, violation.message),
+                    }),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(modules)
+        } else {
+            Err(PublishFailure::Rejected(errors))
+        }
+    }
+
+    /// Returns the packages in the `PackageRegistry` of the account, or none if the account
+    /// has no registry.
+    fn package_registry(&self, account: AccountAddress) -> Vec<PackageMetadataView> {
+        let tag = StructTag {
+            address: CORE_CODE_ADDRESS,
+            module: ident_str!("code").to_owned(),
+            name: ident_str!("PackageRegistry").to_owned(),
+            type_params: vec![],
+        };
+        self.storage
+            .resource(&account, &tag)
+            .and_then(|blob| bcs::from_bytes::<PackageRegistry>(blob).ok())
+            .map(|registry| {
+                registry
+                    .packages
+                    .into_iter()
+                    .filter_map(|metadata| StoredPackage::new(metadata).to_view().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn signer_arg(address: AccountAddress) -> Vec<u8> {
+    MoveValue::Signer(address)
+        .simple_serialize()
+        .expect("BCS for signer")
+}
+
+fn has_init_module(module: &CompiledModule) -> bool {
+    module.function_defs.iter().any(|def| {
+        let handle = module.function_handle_at(def.function);
+        module.identifier_at(handle.name).as_str() == "init_module"
+    })
+}
@@ -0,0 +1,134 @@
+use move_core_types::account_address::AccountAddress;
+use move_core_types::effects::{ChangeSet, Op};
+use move_core_types::language_storage::{ModuleId, StructTag};
+use move_core_types::resolver::{ModuleResolver, ResourceResolver};
+use move_table_extension::{TableChangeSet, TableHandle, TableInfo, TableResolver};
+use std::collections::BTreeMap;
+
+/// A simple in-memory storage which serves as the data store of local VM sessions.
+///
+/// Modules are added directly (bypassing publishing) when a package is loaded, resources
+/// are only created by applying the change set of a finished session. Table items are kept
+/// alongside resources and are created by applying the table change set of a session.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryStorage {
+    modules: BTreeMap<ModuleId, Vec<u8>>,
+    resources: BTreeMap<(AccountAddress, StructTag), Vec<u8>>,
+    tables: BTreeMap<TableHandle, TableInfo>,
+    table_items: BTreeMap<(TableHandle, Vec<u8>), Vec<u8>>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_module(&mut self, module_id: ModuleId, blob: Vec<u8>) {
+        self.modules.insert(module_id, blob);
+    }
+
+    pub fn has_module(&self, module_id: &ModuleId) -> bool {
+        self.modules.contains_key(module_id)
+    }
+
+    pub fn module(&self, module_id: &ModuleId) -> Option<&[u8]> {
+        self.modules.get(module_id).map(|blob| blob.as_slice())
+    }
+
+    pub fn resource(&self, address: &AccountAddress, tag: &StructTag) -> Option<&[u8]> {
+        self.resources
+            .get(&(*address, tag.clone()))
+            .map(|blob| blob.as_slice())
+    }
+
+    /// Returns the key and value types of a table created by a previous session.
+    pub fn table_info(&self, handle: &TableHandle) -> Option<&TableInfo> {
+        self.tables.get(handle)
+    }
+
+    pub fn table_item(&self, handle: &TableHandle, key: &[u8]) -> Option<&[u8]> {
+        self.table_items
+            .get(&(*handle, key.to_vec()))
+            .map(|blob| blob.as_slice())
+    }
+
+    /// Applies the table changes of a finished session to the storage.
+    pub fn apply_table_changes(&mut self, changes: TableChangeSet) {
+        self.tables.extend(changes.new_tables);
+        for handle in changes.removed_tables {
+            self.tables.remove(&handle);
+            self.table_items
+                .retain(|(item_handle, _), _| *item_handle != handle);
+        }
+        for (handle, change) in changes.changes {
+            for (key, op) in change.entries {
+                match op {
+                    Op::New(blob) | Op::Modify(blob) => {
+                        self.table_items.insert((handle, key), blob);
+                    }
+                    Op::Delete => {
+                        self.table_items.remove(&(handle, key));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies the changes of a finished session to the storage.
+    pub fn apply(&mut self, changes: ChangeSet) {
+        for (address, account_changes) in changes.into_inner() {
+            let (modules, resources) = account_changes.into_inner();
+            for (name, op) in modules {
+                let module_id = ModuleId::new(address, name);
+                match op {
+                    Op::New(blob) | Op::Modify(blob) => {
+                        self.modules.insert(module_id, blob);
+                    }
+                    Op::Delete => {
+                        self.modules.remove(&module_id);
+                    }
+                }
+            }
+            for (tag, op) in resources {
+                match op {
+                    Op::New(blob) | Op::Modify(blob) => {
+                        self.resources.insert((address, tag), blob);
+                    }
+                    Op::Delete => {
+                        self.resources.remove(&(address, tag));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl ModuleResolver for InMemoryStorage {
+    type Error = anyhow::Error;
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.modules.get(module_id).cloned())
+    }
+}
+
+impl ResourceResolver for InMemoryStorage {
+    type Error = anyhow::Error;
+
+    fn get_resource(
+        &self,
+        address: &AccountAddress,
+        tag: &StructTag,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.resources.get(&(*address, tag.clone())).cloned())
+    }
+}
+
+impl TableResolver for InMemoryStorage {
+    fn resolve_table_entry(
+        &self,
+        handle: &TableHandle,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        Ok(self.table_items.get(&(*handle, key.to_vec())).cloned())
+    }
+}
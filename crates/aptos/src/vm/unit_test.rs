@@ -0,0 +1,320 @@
+use super::executor::{new_extensions, session_hash};
+use super::{
+    explain_vm_error, AbortExplanation, GasProfile, GasSchedule, InMemoryStorage, ProfilingGasMeter,
+};
+use crate::framwork::natives::{aptos_natives, NativeGasParameters};
+use crate::framwork::{BuiltPackage, RuntimeModuleMetadataV1};
+use crate::toolchain::{ToolchainError, ToolchainResult};
+use move_binary_format::errors::{Location, VMError};
+use move_compiler::unit_test::{ExpectedFailure, ExpectedMoveError, TestCase, TestPlan};
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::ModuleId;
+use move_core_types::vm_status::StatusCode;
+use move_vm_runtime::move_vm::MoveVM;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The outcome of a single unit test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    /// The test behaved as declared, including an expected failure
+    Pass,
+    /// The test failed for any reason other than an unexpected abort, e.g. it did not abort
+    /// although a failure was expected
+    Fail,
+    /// The test aborted unexpectedly or with a different abort code than expected
+    Abort,
+    /// The test exceeded the instruction limit or ran out of gas
+    Timeout,
+}
+
+/// The result of running a single unit test.
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub module: String,
+    pub name: String,
+    pub status: TestStatus,
+    pub expected_failure: bool,
+    /// The VM status the test ended with, if it did not complete successfully
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abort_code: Option<u64>,
+    /// The explanation of the abort code, if the test aborted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abort: Option<AbortExplanation>,
+    /// The gas consumed by the test, if a gas schedule was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_profile: Option<GasProfile>,
+    /// Why the test did not pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// The results of all unit tests which matched the filter.
+#[derive(Debug, Serialize)]
+pub struct TestSummary {
+    pub success: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<TestResult>,
+}
+
+/// Runs the unit tests of a package built with `BuiltPackage::build_for_test`.
+///
+/// Every test runs in its own session on top of a storage which only holds the modules of
+/// the package and its dependencies, so tests cannot observe each other's effects. Only
+/// tests whose fully qualified name (`<module>::<function>`) contains `filter` are run.
+///
+/// Without a gas schedule, every instruction costs one unit of gas and a test times out after
+/// `instruction_limit` instructions. With a gas schedule, tests are charged according to it
+/// and report a gas profile. Storage is never charged, since the changes of tests are
+/// discarded.
+pub fn run_unit_tests(
+    package: &BuiltPackage,
+    test_plan: &TestPlan,
+    filter: Option<&str>,
+    instruction_limit: u64,
+    gas_schedule: Option<&GasSchedule>,
+) -> ToolchainResult<TestSummary> {
+    let vm = MoveVM::new(aptos_natives(NativeGasParameters::zeros())).map_err(|err| {
+        ToolchainError::UnexpectedError(format!(
+            "Failed to create Move VM: {}",
+            err.into_vm_status()
+        ))
+    })?;
+    let mut storage = InMemoryStorage::new();
+    for (module_id, blob) in package.extract_all_code() {
+        storage.add_module(module_id, blob);
+    }
+
+    let mut results = vec![];
+    for (module_id, module_tests) in &test_plan.module_tests {
+        for (name, test) in &module_tests.tests {
+            let qualified_name = format!("{}::{}", module_id.name(), name);
+            if matches!(filter, Some(filter) if !qualified_name.contains(filter)) {
+                continue;
+            }
+            let mut gas_meter = ProfilingGasMeter::new(
+                gas_schedule
+                    .cloned()
+                    .unwrap_or_else(|| GasSchedule::unit(instruction_limit)),
+                format!("{}::{}", module_id, name),
+            );
+            let args = test
+                .arguments
+                .iter()
+                .map(|arg| {
+                    arg.simple_serialize().ok_or_else(|| {
+                        ToolchainError::UnexpectedError(format!(
+                            "Unable to serialize argument {} of test {}",
+                            arg, qualified_name
+                        ))
+                    })
+                })
+                .collect::<ToolchainResult<Vec<_>>>()?;
+            let result = {
+                let txn_hash = session_hash(results.len() as u64);
+                let mut session =
+                    vm.new_session_with_extensions(&storage, new_extensions(&storage, txn_hash));
+                session
+                    .execute_function_bypass_visibility(
+                        module_id,
+                        IdentStr::new(name).map_err(|err| {
+                            ToolchainError::UnexpectedError(format!(
+                                "Invalid test name {}: {}",
+                                name, err
+                            ))
+                        })?,
+                        vec![],
+                        args,
+                        &mut gas_meter,
+                    )
+                    .and_then(|_| session.finish().map(|_| ()))
+            };
+            let mut test_result =
+                check_result(&vm, package.dependency_metadata(), module_id, test, result);
+            if gas_schedule.is_some() {
+                test_result.gas_profile = Some(gas_meter.into_profile());
+            }
+            results.push(test_result);
+        }
+    }
+
+    let passed = results
+        .iter()
+        .filter(|result| result.status == TestStatus::Pass)
+        .count();
+    let failed = results.len() - passed;
+    Ok(TestSummary {
+        success: failed == 0,
+        passed,
+        failed,
+        results,
+    })
+}
+
+/// Compares the result of a test run with the declared expectation.
+fn check_result(
+    vm: &MoveVM,
+    dependency_metadata: &BTreeMap<ModuleId, RuntimeModuleMetadataV1>,
+    module_id: &ModuleId,
+    test: &TestCase,
+    result: Result<(), VMError>,
+) -> TestResult {
+    let mut test_result = TestResult {
+        module: module_id.to_string(),
+        name: test.test_name.clone(),
+        status: TestStatus::Pass,
+        expected_failure: test.expected_failure.is_some(),
+        vm_status: None,
+        abort_code: None,
+        abort: None,
+        gas_profile: None,
+        message: None,
+    };
+    let err = match (result, &test.expected_failure) {
+        (Ok(()), None) => return test_result,
+        (Ok(()), Some(_)) => {
+            test_result.status = TestStatus::Fail;
+            test_result.message = Some("Test did not error as expected".to_string());
+            return test_result;
+        }
+        (Err(err), _) => err,
+    };
+
+    let status = err.major_status();
+    let sub_status = err.sub_status();
+    let location = err.location().clone();
+    test_result.vm_status = Some(format!("{:?}", status));
+    if status == StatusCode::ABORTED {
+        test_result.abort_code = sub_status;
+        test_result.abort = explain_vm_error(vm, dependency_metadata, &err);
+    }
+
+    // A test which runs out of gas may still be expected to fail, so the expectation is
+    // checked first.
+    let mismatch = match &test.expected_failure {
+        None => Some(format!(
+            "Test was not expected to error, but it {}",
+            describe(&err)
+        )),
+        Some(ExpectedFailure::Expected) => None,
+        Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code)) => {
+            if status == StatusCode::ABORTED && sub_status == Some(*code) {
+                None
+            } else {
+                Some(format!(
+                    "Test was expected to abort with code {}, but it {}",
+                    code,
+                    describe(&err)
+                ))
+            }
+        }
+        Some(ExpectedFailure::ExpectedWithError(ExpectedMoveError(
+            expected_status,
+            expected_sub_status,
+            expected_location,
+        ))) => {
+            if status == *expected_status
+                && sub_status == *expected_sub_status
+                && location == *expected_location
+            {
+                None
+            } else {
+                Some(format!(
+                    "Test was expected to error with {:?}{} in {}, but it {}",
+                    expected_status,
+                    expected_sub_status
+                        .map(|code| format!(" (code {})", code))
+                        .unwrap_or_default(),
+                    describe_location(expected_location),
+                    describe(&err)
+                ))
+            }
+        }
+    };
+    let message = match mismatch {
+        Some(message) => message,
+        None => return test_result,
+    };
+    if status == StatusCode::OUT_OF_GAS {
+        test_result.status = TestStatus::Timeout;
+        test_result.message = Some("Test ran out of gas".to_string());
+    } else {
+        test_result.status = if status == StatusCode::ABORTED {
+            TestStatus::Abort
+        } else {
+            TestStatus::Fail
+        };
+        test_result.message = Some(message);
+    }
+    test_result
+}
+
+fn describe(err: &VMError) -> String {
+    match (err.major_status(), err.sub_status()) {
+        (StatusCode::ABORTED, Some(code)) => format!(
+            "aborted with code {} in {}",
+            code,
+            describe_location(err.location())
+        ),
+        (status, _) => format!(
+            "failed with {:?} in {}",
+            status,
+            describe_location(err.location())
+        ),
+    }
+}
+
+fn describe_location(location: &Location) -> String {
+    match location {
+        Location::Undefined => "an undefined location".to_string(),
+        Location::Script => "the script".to_string(),
+        Location::Module(module_id) => format!("module {}", module_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::errors::PartialVMError;
+    use move_core_types::account_address::AccountAddress;
+    use move_core_types::identifier::Identifier;
+
+    fn check(expected_failure: Option<ExpectedFailure>, status: StatusCode) -> TestResult {
+        let vm = MoveVM::new(aptos_natives(NativeGasParameters::zeros())).unwrap();
+        let module_id = ModuleId::new(AccountAddress::ONE, Identifier::new("m").unwrap());
+        let test = TestCase {
+            test_name: "t".to_string(),
+            arguments: vec![],
+            expected_failure,
+        };
+        let err = PartialVMError::new(status).finish(Location::Module(module_id.clone()));
+        check_result(&vm, &BTreeMap::new(), &module_id, &test, Err(err))
+    }
+
+    #[test]
+    fn running_out_of_gas_is_a_timeout_unless_expected() {
+        let result = check(None, StatusCode::OUT_OF_GAS);
+        assert_eq!(result.status, TestStatus::Timeout);
+
+        let result = check(
+            Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(1)),
+            StatusCode::OUT_OF_GAS,
+        );
+        assert_eq!(result.status, TestStatus::Timeout);
+
+        let result = check(Some(ExpectedFailure::Expected), StatusCode::OUT_OF_GAS);
+        assert_eq!(result.status, TestStatus::Pass);
+        assert_eq!(result.message, None);
+    }
+
+    #[test]
+    fn unexpected_errors_fail() {
+        let result = check(None, StatusCode::ARITHMETIC_ERROR);
+        assert_eq!(result.status, TestStatus::Fail);
+        assert_eq!(result.vm_status.as_deref(), Some("ARITHMETIC_ERROR"));
+    }
+}
@@ -0,0 +1,76 @@
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use move_core_types::value::{MoveStruct, MoveValue};
+use serde_json::{json, Map, Value};
+
+/// Converts a Move value into JSON.
+///
+/// Integers which do not fit into a JSON number (`u64` and above) are represented as
+/// strings, addresses as hex literals, `vector<u8>` as a hex string and
+/// `0x1::string::String` as a plain string.
+pub fn move_value_to_json(value: MoveValue) -> Value {
+    match value {
+        MoveValue::U8(v) => json!(v),
+        MoveValue::U16(v) => json!(v),
+        MoveValue::U32(v) => json!(v),
+        MoveValue::U64(v) => json!(v.to_string()),
+        MoveValue::U128(v) => json!(v.to_string()),
+        MoveValue::U256(v) => json!(v.to_string()),
+        MoveValue::Bool(v) => json!(v),
+        MoveValue::Address(v) | MoveValue::Signer(v) => json!(v.to_hex_literal()),
+        MoveValue::Vector(values) => vector_to_json(values),
+        MoveValue::Struct(value) => struct_to_json(value),
+    }
+}
+
+fn vector_to_json(values: Vec<MoveValue>) -> Value {
+    if !values.is_empty() && values.iter().all(|v| matches!(v, MoveValue::U8(_))) {
+        let bytes = values
+            .into_iter()
+            .map(|v| match v {
+                MoveValue::U8(b) => b,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        json!(format!("0x{}", hex::encode(bytes)))
+    } else {
+        Value::Array(values.into_iter().map(move_value_to_json).collect())
+    }
+}
+
+fn struct_to_json(value: MoveStruct) -> Value {
+    match value {
+        MoveStruct::Runtime(fields) => {
+            Value::Array(fields.into_iter().map(move_value_to_json).collect())
+        }
+        MoveStruct::WithFields(fields) => fields_to_json(fields),
+        MoveStruct::WithTypes { type_, fields } => {
+            if type_.address == AccountAddress::ONE
+                && type_.module.as_str() == "string"
+                && type_.name.as_str() == "String"
+            {
+                if let Some((_, MoveValue::Vector(bytes))) = fields.first() {
+                    let bytes = bytes
+                        .iter()
+                        .filter_map(|v| match v {
+                            MoveValue::U8(b) => Some(*b),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    if let Ok(s) = String::from_utf8(bytes) {
+                        return json!(s);
+                    }
+                }
+            }
+            fields_to_json(fields)
+        }
+    }
+}
+
+fn fields_to_json(fields: Vec<(Identifier, MoveValue)>) -> Value {
+    let mut map = Map::new();
+    for (name, value) in fields {
+        map.insert(name.to_string(), move_value_to_json(value));
+    }
+    Value::Object(map)
+}